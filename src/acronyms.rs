@@ -0,0 +1,329 @@
+//! Acronym spelling: the configured token list plus detection of unknown all-caps tokens.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+use crate::lexicon::{AcronymMode, Lexicon, LexiconEntry, attach_suffix};
+use crate::{AbbreviationConfig, ssml};
+
+/// All-caps token with at least two letters, optional trailing digits and an optional
+/// plural or possessive suffix ("APIs", "NASA's", "CPUs'"), plus the hyphen of a compound.
+static RE_CAPS_TOKEN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"\b(?P<base>[A-Z][A-Z0-9]*[A-Z])(?P<digits>[0-9]+)?(?P<suffix>'s|s)?\b(?:(?P<hyphen>-)\b)?",
+    )
+    .unwrap()
+});
+
+/// Consonant clusters English words may start with; anything else reads as letters.
+const ONSETS: &[&str] = &[
+    "bl", "br", "ch", "cl", "cr", "dr", "fl", "fr", "gl", "gn", "gr", "kl", "kn", "kr", "ph", "pl",
+    "pr", "qu", "sc", "sch", "scr", "sh", "shr", "sk", "sl", "sm", "sn", "sp", "spl", "spr", "st",
+    "str", "sw", "th", "thr", "tr", "tw", "wh", "wr",
+];
+
+/// Short English words that turn up in capitals for emphasis and are never acronyms.
+const COMMON_WORDS: &[&str] = &[
+    "A", "AM", "AN", "AND", "ARE", "AS", "AT", "BE", "BUT", "BY", "DO", "FOR", "GO", "HE", "IF",
+    "IN", "IS", "ME", "MY", "NO", "NOT", "OF", "OK", "ON", "OR", "SO", "THE", "TO", "UP", "WAS",
+    "WE", "YES", "YOU",
+];
+
+/// A detector decision and how many times it was applied.
+#[derive(Debug)]
+pub(crate) struct DetectedAcronym {
+    pub(crate) decision: AcronymMode,
+    pub(crate) count: usize,
+}
+
+/// Spell the letters of `word` through the letter sounds, followed by any dotted digit groups.
+fn spell_acronym(
+    word: &str,
+    digits: Option<&str>,
+    cfg: &AbbreviationConfig,
+    lexicon: &Lexicon,
+) -> String {
+    let mut spelled = word
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .map(|c| {
+            let key = c.to_ascii_uppercase().to_string();
+            lexicon
+                .letter_sounds
+                .get(&key)
+                .cloned()
+                .unwrap_or_else(|| key.to_lowercase())
+        })
+        .collect::<Vec<_>>()
+        .join(&cfg.letter_separator);
+    append_digits(&mut spelled, digits, cfg, lexicon);
+    spelled
+}
+
+/// Title-case form of an acronym read as a word ("NASA" becomes "Nasa").
+fn word_form(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+/// Render an acronym match in the given mode, keeping any plural or possessive suffix
+/// attached and turning a compound hyphen ("HTML-based") into a space.
+///
+/// With `ssml`, spelled acronyms stay as characters for the engine to spell and custom
+/// respellings become substitutions.
+fn render_acronym(
+    mode: AcronymMode,
+    word: &str,
+    say: &str,
+    caps: &regex::Captures,
+    cfg: &AbbreviationConfig,
+    lexicon: &Lexicon,
+    ssml: bool,
+) -> String {
+    let suffix = caps.name("suffix").map_or("", |suffix| suffix.as_str());
+    let hyphen = if caps.name("hyphen").is_some() {
+        " "
+    } else {
+        ""
+    };
+    if ssml && mode != AcronymMode::Word {
+        let digits = caps.name("digits").map_or("", |digits| digits.as_str());
+        let marked = match mode {
+            AcronymMode::Custom => {
+                let original = format!("{word}{digits}");
+                let mut spoken = say.to_string();
+                append_digits(&mut spoken, Some(digits), cfg, lexicon);
+                ssml::substitute(&original, &spoken)
+            }
+            _ => ssml::say_as_characters(&format!("{word}{digits}")),
+        };
+        return format!("{marked}{suffix}{hyphen}");
+    }
+    let mut rendered = match mode {
+        AcronymMode::Spell => spell_acronym(word, None, cfg, lexicon),
+        AcronymMode::Word => word_form(word),
+        AcronymMode::Custom => say.to_string(),
+    };
+    append_digits(
+        &mut rendered,
+        caps.name("digits").map(|digits| digits.as_str()),
+        cfg,
+        lexicon,
+    );
+    let mut rendered = attach_suffix(rendered, suffix);
+    rendered.push_str(hyphen);
+    rendered
+}
+
+fn append_digits(
+    spelled: &mut String,
+    digits: Option<&str>,
+    cfg: &AbbreviationConfig,
+    lexicon: &Lexicon,
+) {
+    let Some(digits) = digits.filter(|digits| !digits.trim().is_empty()) else {
+        return;
+    };
+    let number_spelled = digits
+        .split('.')
+        .map(|group| spelled_digit_group(group, cfg, &lexicon.letter_sounds))
+        .filter(|grp| !grp.is_empty())
+        .collect::<Vec<_>>()
+        .join(&cfg.digit_separator);
+    if !number_spelled.is_empty() {
+        if !spelled.is_empty() {
+            spelled.push(' ');
+        }
+        spelled.push_str(&number_spelled);
+    }
+}
+
+pub(crate) fn expand_acronyms(
+    text: &str,
+    cfg: &AbbreviationConfig,
+    lexicon: &Lexicon,
+    ssml: bool,
+) -> String {
+    if lexicon.acronyms.is_empty() {
+        return text.to_string();
+    }
+
+    let mut entries: Vec<_> = lexicon.acronyms.iter().collect();
+    entries.sort_by_key(|entry| Reverse(entry.term.len()));
+
+    let mut result = text.to_string();
+    for entry in entries {
+        let re = entry.pattern_with_infix(r"(?P<digits>\d+(?:\.\d+)*)?");
+        result = ssml::map_unmarked(&result, |plain| {
+            re.replace_all(plain, |caps: &regex::Captures| {
+                if !entry.accepts(&caps["term"]) {
+                    return caps[0].to_string();
+                }
+                let spelled = render_entry(entry, caps, cfg, lexicon, ssml);
+                if spelled.is_empty() {
+                    caps[0].to_string()
+                } else {
+                    spelled
+                }
+            })
+            .to_string()
+        });
+    }
+    result
+}
+
+fn render_entry(
+    entry: &LexiconEntry,
+    caps: &regex::Captures,
+    cfg: &AbbreviationConfig,
+    lexicon: &Lexicon,
+    ssml: bool,
+) -> String {
+    // Custom respellings without a `say` fall back to spelling.
+    let mode = if entry.mode == AcronymMode::Custom && entry.say.is_empty() {
+        AcronymMode::Spell
+    } else {
+        entry.mode
+    };
+    render_acronym(mode, &caps["term"], &entry.say, caps, cfg, lexicon, ssml)
+}
+
+/// Record a spell or word decision for each all-caps token that is not in the acronym list,
+/// and apply it when `detect_unknown` is set.
+pub(crate) fn expand_unknown_acronyms(
+    text: &str,
+    cfg: &AbbreviationConfig,
+    lexicon: &Lexicon,
+    detected: &mut BTreeMap<String, DetectedAcronym>,
+    ssml: bool,
+) -> String {
+    text.lines()
+        .map(|line| {
+            if is_shouted(line) {
+                return line.to_string();
+            }
+            ssml::map_unmarked(line, |plain| {
+                RE_CAPS_TOKEN
+                    .replace_all(plain, |caps: &regex::Captures| {
+                        let base = &caps["base"];
+                        if base.chars().filter(|c| c.is_ascii_alphabetic()).count()
+                            < cfg.detect_min_len
+                            || COMMON_WORDS.contains(&base)
+                            || is_known(base, lexicon)
+                            || is_roman_numeral(base)
+                        {
+                            return caps[0].to_string();
+                        }
+                        let decision = decide(base);
+                        detected
+                            .entry(base.to_string())
+                            .or_insert(DetectedAcronym { decision, count: 0 })
+                            .count += 1;
+                        if !cfg.detect_unknown {
+                            return caps[0].to_string();
+                        }
+                        render_acronym(decision, base, "", caps, cfg, lexicon, ssml)
+                    })
+                    .to_string()
+            })
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn decide(base: &str) -> AcronymMode {
+    let letters: String = base.chars().filter(|c| c.is_ascii_alphabetic()).collect();
+    if looks_pronounceable(&letters) {
+        AcronymMode::Word
+    } else {
+        AcronymMode::Spell
+    }
+}
+
+/// Vowel/consonant heuristic: long enough, a valid onset, no awkward consonant or vowel runs,
+/// and roughly one vowel per four letters.
+fn looks_pronounceable(letters: &str) -> bool {
+    let letters: Vec<char> = letters.to_ascii_lowercase().chars().collect();
+    if letters.len() < 4 {
+        return false;
+    }
+    let is_vowel = |idx: usize| {
+        let c = letters[idx];
+        "aeiou".contains(c) || (c == 'y' && idx > 0)
+    };
+    let vowels = (0..letters.len()).filter(|&idx| is_vowel(idx)).count();
+    if vowels == 0 || vowels * 4 < letters.len() {
+        return false;
+    }
+    let onset: String = (0..letters.len())
+        .take_while(|&idx| !is_vowel(idx))
+        .map(|idx| letters[idx])
+        .collect();
+    if onset.len() > 1 && !ONSETS.contains(&onset.as_str()) {
+        return false;
+    }
+    // A medial run of three consonants is fine when it ends in a valid onset ("FORTRAN").
+    let mut consonant_run = 0;
+    let mut vowel_run = 0;
+    for idx in onset.len()..letters.len() {
+        if is_vowel(idx) {
+            vowel_run += 1;
+            consonant_run = 0;
+        } else {
+            consonant_run += 1;
+            vowel_run = 0;
+        }
+        let tail: String = letters[idx.saturating_sub(1)..=idx].iter().collect();
+        if consonant_run > 3
+            || (consonant_run == 3 && !ONSETS.contains(&tail.as_str()))
+            || vowel_run > 2
+        {
+            return false;
+        }
+    }
+    true
+}
+
+fn is_known(base: &str, lexicon: &Lexicon) -> bool {
+    lexicon
+        .acronyms
+        .iter()
+        .chain(lexicon.brands.iter())
+        .any(|entry| entry.term.eq_ignore_ascii_case(base))
+}
+
+/// Chapter and list numbering such as "XII" or "IV" is not an acronym.
+fn is_roman_numeral(base: &str) -> bool {
+    base.chars().all(|c| matches!(c, 'I' | 'V' | 'X'))
+}
+
+/// Headings and shouted lines are mostly uppercase; spelling their words would be wrong.
+fn is_shouted(line: &str) -> bool {
+    let letters = line.chars().filter(|c| c.is_alphabetic()).count();
+    let upper = line.chars().filter(|c| c.is_uppercase()).count();
+    letters >= 8 && upper * 10 >= letters * 6
+}
+
+fn spelled_digit_group(
+    group: &str,
+    cfg: &AbbreviationConfig,
+    letter_sounds: &BTreeMap<String, String>,
+) -> String {
+    group
+        .chars()
+        .filter(|c| c.is_ascii_digit())
+        .map(|c| {
+            let key = c.to_string();
+            letter_sounds.get(&key).cloned().unwrap_or(key)
+        })
+        .collect::<Vec<_>>()
+        .join(&cfg.letter_separator)
+}
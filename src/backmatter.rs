@@ -0,0 +1,314 @@
+//! Back matter.
+//!
+//! Reference lists, bibliographies and endnote sections are minutes of unlistenable audio.
+//! A section is recognized by its heading ("References", "7. Bibliography", "WORKS CITED")
+//! and confirmed by the shape of its entries (DOIs, URLs, "Retrieved from", journal volume
+//! and page ranges, an author followed by a year and a title), then dropped or replaced by a
+//! one-line summary. It runs on the whole document before chapters are split.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::chapters::{self, ChapterConfig};
+
+/// A heading line: optional Markdown hashes and section number, optional trailing colon.
+static RE_HEADING: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^\s{0,3}(?P<hashes>#{1,6}\s+)?(?:(?:\d+|[IVXivx]+)\.?\s+)?(?P<title>[^\s#].*?)\s*:?\s*#*\s*$",
+    )
+    .unwrap()
+});
+/// What reference-list entries look like.
+static RE_ENTRY_SHAPE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?ix)
+        \b10\.\d{4,9}/\S+ | \bdoi: | https?:// | \b(?:retrieved\ from|available\ at|accessed)\b
+        | \b\d+\s*\(\d+\)\s*[,:]\s*\d+ | \bvol\.\s*\d+ | \bpp?\.\s*\d+
+        | \bISBN\b | \buniversity\ press\b | \beds?\. | ^\s*\[\d+\]
+        ",
+    )
+    .unwrap()
+});
+/// An author, then a year, then a title: `Lie, H. W. (1994). Cascading ...`,
+/// `Lie, Håkon Wium. 1994. Cascading ...`.
+static RE_AUTHOR_YEAR: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^(?:\[\d+\]\s*|\d+\.\s*)?\p{Lu}[\p{L}'’-]+,\ \p{Lu}[^()]*?(?:\ \((?:1[5-9]|20)\d\d[a-z]?\)|\.\ (?:1[5-9]|20)\d\d[a-z]?)\.?\ +\S",
+    )
+    .unwrap()
+});
+/// A horizontal rule that may set off an untitled reference list.
+static RE_RULE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s*(?:[-_=*]\s*){3,}$").unwrap());
+/// A numbered entry: `[12] ...`, `12. ...`.
+static RE_NUMBERED_ENTRY: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s*(?:\[\d+\]|\d+\.)\s").unwrap());
+
+/// An untitled list after the last rule needs at least this many entries.
+const MIN_UNTITLED_ENTRIES: usize = 5;
+/// A section needs this many reference-shaped entries in a row (or all of them, if it has
+/// fewer).
+const MIN_REFERENCE_RUN: usize = 3;
+/// A heading without Markdown hashes must be a standalone line at most this long.
+const MAX_PLAIN_HEADING_CHARS: usize = 40;
+
+/// `[back_matter]`: reference sections at the end of papers and books.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub(crate) struct BackMatterConfig {
+    /// Recognize back-matter sections.
+    detect: bool,
+    /// What happens to a recognized section.
+    mode: BackMatterMode,
+    /// Headings that open a back-matter section (case-insensitive, whole heading).
+    #[serde(default = "BackMatterConfig::default_headings")]
+    headings: Vec<String>,
+    /// Headings that are never stripped and end a stripped section (matched as a prefix,
+    /// so "Appendix" keeps "Appendix A").
+    keep_headings: Vec<String>,
+    /// Share of a section's entries that must look like references.
+    min_reference_ratio: f32,
+    /// Text replacing a section in `summarize` mode; `{heading}` and `{count}` are filled in.
+    summary: String,
+}
+
+impl Default for BackMatterConfig {
+    fn default() -> Self {
+        Self {
+            detect: true,
+            mode: BackMatterMode::Drop,
+            headings: Self::default_headings(),
+            keep_headings: [
+                "Appendix",
+                "Acknowledgments",
+                "Acknowledgements",
+                "About the Author",
+            ]
+            .map(str::to_string)
+            .to_vec(),
+            min_reference_ratio: 0.5,
+            summary: "{heading} omitted, {count} entries.".to_string(),
+        }
+    }
+}
+
+impl BackMatterConfig {
+    fn default_headings() -> Vec<String> {
+        [
+            "References",
+            "Bibliography",
+            "Works Cited",
+            "Literature Cited",
+            "Sources",
+            "Notes",
+            "Endnotes",
+            "Further Reading",
+        ]
+        .map(str::to_string)
+        .to_vec()
+    }
+
+    fn is_back_matter(&self, title: &str) -> bool {
+        self.headings
+            .iter()
+            .any(|heading| heading.eq_ignore_ascii_case(title))
+            && !self.is_kept(title)
+    }
+
+    fn is_kept(&self, title: &str) -> bool {
+        let title = title.to_lowercase();
+        self.keep_headings
+            .iter()
+            .any(|heading| title.starts_with(&heading.to_lowercase()))
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+enum BackMatterMode {
+    /// Remove the section, heading included.
+    #[default]
+    Drop,
+    /// Replace the section with `back_matter.summary`.
+    Summarize,
+}
+
+/// A section that was removed.
+#[derive(Debug)]
+pub(crate) struct RemovedSection {
+    pub(crate) heading: String,
+    pub(crate) entries: usize,
+}
+
+/// Drop or summarize the back-matter sections of `text`, and a reference list set off by a
+/// rule at the end of the text without a heading. A section ends at the next chapter heading
+/// `chapters::split` would detect.
+pub(crate) fn strip(
+    text: &str,
+    cfg: &BackMatterConfig,
+    chapters: &ChapterConfig,
+) -> (String, Vec<RemovedSection>) {
+    let mut removed = Vec::new();
+    if !cfg.detect || cfg.headings.is_empty() {
+        return (text.to_string(), removed);
+    }
+    let lines: Vec<&str> = text.lines().collect();
+    let mut out: Vec<String> = Vec::with_capacity(lines.len());
+    let mut idx = 0;
+    while idx < lines.len() {
+        let Some((title, level)) =
+            heading(&lines, idx).filter(|(title, _)| cfg.is_back_matter(title))
+        else {
+            out.push(lines[idx].to_string());
+            idx += 1;
+            continue;
+        };
+        let end = section_end(&lines, idx + 1, level, cfg, chapters);
+        let entries = entries(&lines[idx + 1..end]);
+        if !is_reference_list(&entries, cfg) {
+            out.push(lines[idx].to_string());
+            idx += 1;
+            continue;
+        }
+        if cfg.mode == BackMatterMode::Summarize {
+            out.push(
+                cfg.summary
+                    .replace("{heading}", &title)
+                    .replace("{count}", &entries.len().to_string()),
+            );
+            out.push(String::new());
+        }
+        removed.push(RemovedSection {
+            heading: title,
+            entries: entries.len(),
+        });
+        idx = end;
+    }
+    strip_untitled_list(&mut out, cfg, &mut removed);
+    (out.join("\n"), removed)
+}
+
+fn strip_untitled_list(
+    lines: &mut Vec<String>,
+    cfg: &BackMatterConfig,
+    removed: &mut Vec<RemovedSection>,
+) {
+    let Some(rule) = lines.iter().rposition(|line| RE_RULE.is_match(line)) else {
+        return;
+    };
+    let tail: Vec<&str> = lines[rule + 1..].iter().map(String::as_str).collect();
+    let entries = entries(&tail);
+    if entries.len() < MIN_UNTITLED_ENTRIES || !is_reference_list(&entries, cfg) {
+        return;
+    }
+    let heading = "Reference list".to_string();
+    lines.truncate(rule);
+    if cfg.mode == BackMatterMode::Summarize {
+        lines.push(String::new());
+        lines.push(
+            cfg.summary
+                .replace("{heading}", &heading)
+                .replace("{count}", &entries.len().to_string()),
+        );
+    }
+    removed.push(RemovedSection {
+        heading,
+        entries: entries.len(),
+    });
+}
+
+/// Whether enough entries look like references, with a run of them in a row.
+fn is_reference_list(entries: &[String], cfg: &BackMatterConfig) -> bool {
+    let shaped: Vec<bool> = entries
+        .iter()
+        .map(|entry| RE_ENTRY_SHAPE.is_match(entry) || RE_AUTHOR_YEAR.is_match(entry))
+        .collect();
+    let references = shaped.iter().filter(|&&shaped| shaped).count();
+    let longest_run = shaped
+        .split(|&shaped| !shaped)
+        .map(<[bool]>::len)
+        .max()
+        .unwrap_or(0);
+    !entries.is_empty()
+        && references as f32 >= entries.len() as f32 * cfg.min_reference_ratio
+        && longest_run >= MIN_REFERENCE_RUN.min(entries.len())
+}
+
+/// The title of the heading at `lines[idx]` and its Markdown level (0 for a plain line).
+/// A plain line is only a heading when it is short and stands alone between blank lines.
+fn heading(lines: &[&str], idx: usize) -> Option<(String, usize)> {
+    let caps = RE_HEADING.captures(lines[idx])?;
+    let level = caps
+        .name("hashes")
+        .map_or(0, |hashes| hashes.as_str().trim().len());
+    if level == 0 {
+        let blank_before = idx == 0 || lines[idx - 1].trim().is_empty();
+        let blank_after = lines.get(idx + 1).is_none_or(|next| next.trim().is_empty());
+        if !blank_before
+            || !blank_after
+            || lines[idx].trim().chars().count() > MAX_PLAIN_HEADING_CHARS
+        {
+            return None;
+        }
+    }
+    Some((caps["title"].to_string(), level))
+}
+
+/// A section runs to the next Markdown heading of the same or a higher level, the next
+/// chapter heading, the next kept or back-matter heading, or the end of the text.
+fn section_end(
+    lines: &[&str],
+    start: usize,
+    level: usize,
+    cfg: &BackMatterConfig,
+    chapters: &ChapterConfig,
+) -> usize {
+    (start..lines.len())
+        .find(|&idx| {
+            if chapters::is_heading(lines, idx, chapters) {
+                return true;
+            }
+            let Some((title, next_level)) = heading(lines, idx) else {
+                return false;
+            };
+            (next_level > 0 && (level == 0 || next_level <= level))
+                || cfg.is_kept(&title)
+                || cfg.is_back_matter(&title)
+        })
+        .unwrap_or(lines.len())
+}
+
+/// Split a section into entries: paragraphs when entries are separated by blank lines,
+/// otherwise lines, with indented lines continuing the entry above.
+fn entries(lines: &[&str]) -> Vec<String> {
+    let first = lines.iter().position(|line| !line.trim().is_empty());
+    let last = lines.iter().rposition(|line| !line.trim().is_empty());
+    let content = match (first, last) {
+        (Some(first), Some(last)) => &lines[first..=last],
+        _ => return Vec::new(),
+    };
+    let blank_separated = content.iter().any(|line| line.trim().is_empty());
+    let mut entries: Vec<String> = Vec::new();
+    let mut previous_blank = true;
+    for &line in content {
+        if line.trim().is_empty() {
+            previous_blank = true;
+            continue;
+        }
+        let continues = if blank_separated {
+            !previous_blank
+        } else {
+            line.starts_with([' ', '\t']) && !RE_NUMBERED_ENTRY.is_match(line)
+        };
+        match entries.last_mut() {
+            Some(entry) if continues => {
+                entry.push(' ');
+                entry.push_str(line.trim());
+            }
+            _ => entries.push(line.trim().to_string()),
+        }
+        previous_blank = false;
+    }
+    entries
+}
@@ -0,0 +1,401 @@
+//! Chapter detection and per-chapter output.
+//!
+//! Headings are recognized on the raw input (Markdown `#`, "Chapter 12", "CHAPTER XII",
+//! "Part One", centered all-caps lines), rewritten as Markdown headings so the pause model
+//! treats them alike, and optionally used to split the book into numbered output files.
+
+use anyhow::{Context, Result};
+use log::info;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::simple_number_to_words;
+
+/// `Chapter 12`, `CHAPTER XII`, `Part One: The Storm`; the title needs a separator so a
+/// sentence starting with "Part one of ..." is not a heading.
+static RE_NUMBERED: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)^(?P<keyword>[a-z]+)\s+(?P<number>\d+|[ivxlcdm]+|[a-z]+(?:[- ][a-z]+)?)\s*(?:(?:[.:]|\s[-–—])\s*(?P<title>.*?))?\s*$",
+    )
+    .unwrap()
+});
+static RE_MARKDOWN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s{0,3}(?P<level>#{1,6})[ \t]+(?P<title>.*?)(?:[ \t]+#+)?[ \t]*$").unwrap()
+});
+
+/// `[chapters]`: chapter heading detection and per-chapter output files.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub(crate) struct ChapterConfig {
+    /// Recognize chapter headings and turn them into headings.
+    detect: bool,
+    /// Words that introduce a numbered heading ("Chapter 12", "Part One").
+    keywords: Vec<String>,
+    /// Headings recognized on their own, without a number.
+    unnumbered: Vec<String>,
+    /// Markdown headings up to this level (`#` = 1) start a chapter (0 disables).
+    markdown_max_level: usize,
+    /// Indentation that marks an all-caps line as a centered heading (0 disables).
+    centered_min_indent: usize,
+    /// Longest line (in chars) considered a heading.
+    max_heading_chars: usize,
+    /// Replace numbered headings with a spoken announcement ("Chapter twelve").
+    announce: bool,
+    /// Write one numbered file per chapter next to `--output` instead of a single file.
+    split_output: bool,
+    /// Write `<output stem>.manifest.json` listing chapter files, titles and char counts.
+    write_manifest: bool,
+}
+
+impl Default for ChapterConfig {
+    fn default() -> Self {
+        Self {
+            detect: true,
+            keywords: ["Chapter", "Part", "Book"].map(str::to_string).to_vec(),
+            unnumbered: ["Prologue", "Epilogue", "Preface", "Foreword", "Afterword"]
+                .map(str::to_string)
+                .to_vec(),
+            markdown_max_level: 1,
+            centered_min_indent: 10,
+            max_heading_chars: 80,
+            announce: false,
+            split_output: false,
+            write_manifest: true,
+        }
+    }
+}
+
+/// A chapter of the raw input; `text` starts with its (rewritten) heading.
+#[derive(Debug)]
+pub(crate) struct Chapter {
+    pub(crate) title: Option<String>,
+    pub(crate) text: String,
+}
+
+/// One line of the manifest.
+#[derive(Debug, Serialize)]
+struct ManifestEntry {
+    index: usize,
+    file: String,
+    title: Option<String>,
+    chars: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct Manifest<'a> {
+    source: String,
+    chapters: &'a [ManifestEntry],
+}
+
+impl ChapterConfig {
+    pub(crate) fn split_output(&self) -> bool {
+        self.detect && self.split_output
+    }
+}
+
+/// Split `text` at chapter headings. Text before the first heading becomes an untitled
+/// chapter; with detection off the whole input is one chapter.
+pub(crate) fn split(text: &str, cfg: &ChapterConfig) -> Vec<Chapter> {
+    let mut chapters = Vec::new();
+    let mut current = Chapter {
+        title: None,
+        text: String::new(),
+    };
+    let lines: Vec<&str> = text.lines().collect();
+    for (idx, line) in lines.iter().enumerate() {
+        match heading_at(&lines, idx, cfg) {
+            Some((title, spoken)) => {
+                if current.title.is_some() || !current.text.trim().is_empty() {
+                    chapters.push(current);
+                }
+                current = Chapter {
+                    title: Some(title),
+                    text: format!("# {spoken}\n"),
+                };
+            }
+            None => {
+                current.text.push_str(line);
+                current.text.push('\n');
+            }
+        }
+    }
+    if current.title.is_some() || !current.text.trim().is_empty() || chapters.is_empty() {
+        chapters.push(current);
+    }
+    chapters
+}
+
+/// Whether `lines[idx]` is a heading [`split`] would start a chapter at.
+pub(crate) fn is_heading(lines: &[&str], idx: usize, cfg: &ChapterConfig) -> bool {
+    heading_at(lines, idx, cfg).is_some()
+}
+
+/// The heading at `lines[idx]`, if any; headings need a blank line (or the start) above.
+fn heading_at(lines: &[&str], idx: usize, cfg: &ChapterConfig) -> Option<(String, String)> {
+    let blank_before = idx == 0 || lines[idx - 1].trim().is_empty();
+    let blank_after = lines.get(idx + 1).is_none_or(|next| next.trim().is_empty());
+    if cfg.detect && blank_before {
+        detect_heading(lines[idx], blank_after, cfg)
+    } else {
+        None
+    }
+}
+
+/// Recognize a heading line, returning its title and the heading text to keep.
+fn detect_heading(line: &str, blank_after: bool, cfg: &ChapterConfig) -> Option<(String, String)> {
+    let trimmed = line.trim();
+    if trimmed.is_empty() || trimmed.chars().count() > cfg.max_heading_chars {
+        return None;
+    }
+
+    if let Some(caps) = RE_MARKDOWN.captures(line) {
+        if caps["level"].len() > cfg.markdown_max_level {
+            return None;
+        }
+        let title = caps["title"].to_string();
+        let spoken = numbered_heading(&title, cfg).unwrap_or_else(|| title.clone());
+        return Some((title, spoken));
+    }
+
+    if let Some(spoken) = numbered_heading(trimmed, cfg) {
+        return Some((trimmed.to_string(), spoken));
+    }
+
+    if cfg.unnumbered.iter().any(|word| {
+        trimmed
+            .trim_end_matches(['.', ':'])
+            .eq_ignore_ascii_case(word)
+    }) {
+        return Some((trimmed.to_string(), title_case(trimmed)));
+    }
+
+    let indent = line.len() - line.trim_start().len();
+    if cfg.centered_min_indent > 0
+        && indent >= cfg.centered_min_indent
+        && blank_after
+        && is_all_caps(trimmed)
+    {
+        return Some((trimmed.to_string(), title_case(trimmed)));
+    }
+    None
+}
+
+/// "Chapter 12: The Storm" as it should be spoken: the number in words when announcing,
+/// and all-caps titles in title case.
+fn numbered_heading(text: &str, cfg: &ChapterConfig) -> Option<String> {
+    let caps = RE_NUMBERED.captures(text)?;
+    let keyword = cfg
+        .keywords
+        .iter()
+        .find(|keyword| keyword.eq_ignore_ascii_case(&caps["keyword"]))?;
+    let number = parse_number(&caps["number"])?;
+    let title = caps.name("title").map_or("", |title| title.as_str());
+    let title = title_case(title);
+
+    let mut spoken = if cfg.announce {
+        format!("{keyword} {}", simple_number_to_words(number))
+    } else {
+        format!("{} {}", title_case(&caps["keyword"]), &caps["number"])
+    };
+    if !title.is_empty() {
+        spoken.push_str(": ");
+        spoken.push_str(&title);
+    }
+    Some(spoken)
+}
+
+/// Parse "12", "XII" or "Twenty-One".
+fn parse_number(text: &str) -> Option<usize> {
+    if let Ok(number) = text.parse() {
+        return Some(number);
+    }
+    parse_roman(text).or_else(|| parse_number_words(text))
+}
+
+fn parse_roman(text: &str) -> Option<usize> {
+    let value = |c: char| match c.to_ascii_uppercase() {
+        'I' => Some(1),
+        'V' => Some(5),
+        'X' => Some(10),
+        'L' => Some(50),
+        'C' => Some(100),
+        'D' => Some(500),
+        'M' => Some(1000),
+        _ => None,
+    };
+    let digits = text.chars().map(value).collect::<Option<Vec<usize>>>()?;
+    let mut total = 0;
+    for (idx, digit) in digits.iter().enumerate() {
+        if digits.get(idx + 1).is_some_and(|next| next > digit) {
+            total -= *digit as isize;
+        } else {
+            total += *digit as isize;
+        }
+    }
+    // Reject sequences such as "IIII" or "VX" that only look like numerals.
+    (total > 0 && to_roman(total as usize).eq_ignore_ascii_case(text)).then_some(total as usize)
+}
+
+fn to_roman(mut number: usize) -> String {
+    const NUMERALS: [(usize, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut roman = String::new();
+    for (value, numeral) in NUMERALS {
+        while number >= value {
+            roman.push_str(numeral);
+            number -= value;
+        }
+    }
+    roman
+}
+
+fn parse_number_words(text: &str) -> Option<usize> {
+    const UNITS: [&str; 20] = [
+        "zero",
+        "one",
+        "two",
+        "three",
+        "four",
+        "five",
+        "six",
+        "seven",
+        "eight",
+        "nine",
+        "ten",
+        "eleven",
+        "twelve",
+        "thirteen",
+        "fourteen",
+        "fifteen",
+        "sixteen",
+        "seventeen",
+        "eighteen",
+        "nineteen",
+    ];
+    const TENS: [&str; 10] = [
+        "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+    ];
+    let lower = text.to_lowercase();
+    let mut words = lower.split(['-', ' ']);
+    let first = words.next()?;
+    let second = words.next();
+    if words.next().is_some() {
+        return None;
+    }
+    if let Some(unit) = UNITS.iter().position(|word| *word == first) {
+        return second.is_none().then_some(unit);
+    }
+    let tens = TENS
+        .iter()
+        .position(|word| !word.is_empty() && *word == first)?
+        * 10;
+    match second {
+        None => Some(tens),
+        Some(second) => UNITS[1..10]
+            .iter()
+            .position(|word| *word == second)
+            .map(|unit| tens + unit + 1),
+    }
+}
+
+fn is_all_caps(text: &str) -> bool {
+    let letters = text.chars().filter(|c| c.is_alphabetic()).count();
+    letters >= 3 && !text.chars().any(char::is_lowercase)
+}
+
+fn title_case(text: &str) -> String {
+    if !is_all_caps(text) {
+        return text.to_string();
+    }
+    text.split(' ')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first
+                    .to_uppercase()
+                    .chain(chars.flat_map(char::to_lowercase))
+                    .collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `book.txt` becomes `book-01.txt`, `book-02.txt`, ...
+fn chapter_path(output: &Path, index: usize, width: usize) -> PathBuf {
+    let stem = output
+        .file_stem()
+        .map_or_else(|| "output".into(), |stem| stem.to_string_lossy());
+    let name = match output.extension() {
+        Some(ext) => format!("{stem}-{index:0width$}.{}", ext.to_string_lossy()),
+        None => format!("{stem}-{index:0width$}"),
+    };
+    output.with_file_name(name)
+}
+
+/// Write `<output stem>.manifest.json` and return its path.
+fn write_manifest(output: &Path, source: &Path, entries: &[ManifestEntry]) -> Result<PathBuf> {
+    let stem = output
+        .file_stem()
+        .map_or_else(|| "output".into(), |stem| stem.to_string_lossy());
+    let path = output.with_file_name(format!("{stem}.manifest.json"));
+    let manifest = Manifest {
+        source: source.display().to_string(),
+        chapters: entries,
+    };
+    let json = serde_json::to_string_pretty(&manifest).context("serializing chapter manifest")?;
+    fs::write(&path, json + "\n").with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Write one numbered file per chapter next to `output`, plus the manifest.
+pub(crate) fn write_chapters(
+    output: &Path,
+    input: &Path,
+    chapters: &[Chapter],
+    cleaned: &[String],
+    cfg: &ChapterConfig,
+) -> Result<()> {
+    let width = cleaned.len().to_string().len().max(2);
+    let mut entries = Vec::new();
+    for (idx, (chapter, text)) in chapters.iter().zip(cleaned).enumerate() {
+        let path = chapter_path(output, idx + 1, width);
+        fs::write(&path, text).with_context(|| format!("Failed to write {}", path.display()))?;
+        entries.push(ManifestEntry {
+            index: idx + 1,
+            file: path
+                .file_name()
+                .map_or_else(String::new, |name| name.to_string_lossy().into_owned()),
+            title: chapter.title.clone(),
+            chars: text.chars().count(),
+        });
+    }
+    info!(
+        "Wrote {} chapter files next to {}",
+        entries.len(),
+        output.display()
+    );
+    if cfg.write_manifest {
+        let manifest = write_manifest(output, input, &entries)?;
+        info!("Wrote chapter manifest to {}", manifest.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,454 @@
+//! Letters the voice cannot read.
+//!
+//! XTTS models are trained per language, so a stray Cyrillic word, a CJK name or an accented
+//! name such as "Håkon" in English text comes out as garbage or silence. This stage finds the
+//! letters outside the target language's alphabet and reports where they are; it can also
+//! transliterate them (folding Latin diacritics, romanizing Cyrillic and Greek) or remove them.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+/// Letters that fold to more than their base letter, or that have no decomposition.
+const LATIN_SPECIAL: &[(char, &str)] = &[
+    ('ß', "ss"),
+    ('ẞ', "SS"),
+    ('æ', "ae"),
+    ('Æ', "AE"),
+    ('œ', "oe"),
+    ('Œ', "OE"),
+    ('ø', "o"),
+    ('Ø', "O"),
+    ('ł', "l"),
+    ('Ł', "L"),
+    ('đ', "d"),
+    ('Đ', "D"),
+    ('ð', "d"),
+    ('Ð', "D"),
+    ('þ', "th"),
+    ('Þ', "Th"),
+    ('ı', "i"),
+    ('ħ', "h"),
+    ('Ħ', "H"),
+    ('ŋ', "ng"),
+    ('Ŋ', "Ng"),
+];
+
+/// Basic Cyrillic romanization (Russian, with the extra Ukrainian and Belarusian letters).
+const CYRILLIC: &[(char, &str)] = &[
+    ('а', "a"),
+    ('б', "b"),
+    ('в', "v"),
+    ('г', "g"),
+    ('д', "d"),
+    ('е', "e"),
+    ('ё', "yo"),
+    ('ж', "zh"),
+    ('з', "z"),
+    ('и', "i"),
+    ('й', "y"),
+    ('к', "k"),
+    ('л', "l"),
+    ('м', "m"),
+    ('н', "n"),
+    ('о', "o"),
+    ('п', "p"),
+    ('р', "r"),
+    ('с', "s"),
+    ('т', "t"),
+    ('у', "u"),
+    ('ф', "f"),
+    ('х', "kh"),
+    ('ц', "ts"),
+    ('ч', "ch"),
+    ('ш', "sh"),
+    ('щ', "shch"),
+    ('ъ', ""),
+    ('ы', "y"),
+    ('ь', ""),
+    ('э', "e"),
+    ('ю', "yu"),
+    ('я', "ya"),
+    ('і', "i"),
+    ('ї', "yi"),
+    ('є', "ye"),
+    ('ґ', "g"),
+    ('ў', "u"),
+];
+
+/// Basic Greek romanization, after accents are folded away.
+const GREEK: &[(char, &str)] = &[
+    ('α', "a"),
+    ('β', "v"),
+    ('γ', "g"),
+    ('δ', "d"),
+    ('ε', "e"),
+    ('ζ', "z"),
+    ('η', "i"),
+    ('θ', "th"),
+    ('ι', "i"),
+    ('κ', "k"),
+    ('λ', "l"),
+    ('μ', "m"),
+    ('ν', "n"),
+    ('ξ', "x"),
+    ('ο', "o"),
+    ('π', "p"),
+    ('ρ', "r"),
+    ('σ', "s"),
+    ('ς', "s"),
+    ('τ', "t"),
+    ('υ', "y"),
+    ('φ', "f"),
+    ('χ', "ch"),
+    ('ψ', "ps"),
+    ('ω', "o"),
+];
+
+/// `[charset]`: letters outside the target language's alphabet.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub(crate) struct CharsetConfig {
+    /// Language the voice is trained on; its alphabet and plain ASCII are supported.
+    language: Language,
+    /// Letters accepted in addition to the language's alphabet.
+    extra_letters: String,
+    /// What happens to unsupported letters once they are reported.
+    action: CharsetAction,
+    /// How many positions are listed for each letter in the log and the report.
+    max_positions: usize,
+}
+
+impl Default for CharsetConfig {
+    fn default() -> Self {
+        Self {
+            language: Language::En,
+            extra_letters: String::new(),
+            action: CharsetAction::Report,
+            max_positions: 5,
+        }
+    }
+}
+
+impl CharsetConfig {
+    /// Whether `ch` is a letter the voice cannot read.
+    fn is_unsupported(&self, ch: char) -> bool {
+        ch.is_alphabetic()
+            && !ch.is_ascii()
+            && !self.language.has_letter(ch)
+            && !self.extra_letters.contains(ch)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+enum Language {
+    /// English.
+    #[default]
+    En,
+    /// Spanish.
+    Es,
+    /// French.
+    Fr,
+    /// German.
+    De,
+    /// Italian.
+    It,
+    /// Portuguese.
+    Pt,
+    /// Polish.
+    Pl,
+    /// Turkish.
+    Tr,
+    /// Russian.
+    Ru,
+    /// Dutch.
+    Nl,
+    /// Czech.
+    Cs,
+    /// Arabic.
+    Ar,
+    /// Simplified Chinese.
+    ZhCn,
+    /// Japanese.
+    Ja,
+    /// Hungarian.
+    Hu,
+    /// Korean.
+    Ko,
+    /// Hindi.
+    Hi,
+}
+
+impl Language {
+    fn name(self) -> &'static str {
+        match self {
+            Language::En => "English",
+            Language::Es => "Spanish",
+            Language::Fr => "French",
+            Language::De => "German",
+            Language::It => "Italian",
+            Language::Pt => "Portuguese",
+            Language::Pl => "Polish",
+            Language::Tr => "Turkish",
+            Language::Ru => "Russian",
+            Language::Nl => "Dutch",
+            Language::Cs => "Czech",
+            Language::Ar => "Arabic",
+            Language::ZhCn => "Chinese",
+            Language::Ja => "Japanese",
+            Language::Hu => "Hungarian",
+            Language::Ko => "Korean",
+            Language::Hi => "Hindi",
+        }
+    }
+
+    /// Whether `ch`, a non-ASCII letter, belongs to the language's alphabet or script.
+    fn has_letter(self, ch: char) -> bool {
+        let code = ch as u32;
+        let han =
+            matches!(code, 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF | 0x20000..=0x2FA1F);
+        match self {
+            Language::En => false,
+            Language::Es => "áéíóúüñÁÉÍÓÚÜÑ".contains(ch),
+            Language::Fr => "àâæçéèêëîïôœùûüÿÀÂÆÇÉÈÊËÎÏÔŒÙÛÜŸ".contains(ch),
+            Language::De => "äöüßÄÖÜẞ".contains(ch),
+            Language::It => "àèéìíîòóùúÀÈÉÌÍÎÒÓÙÚ".contains(ch),
+            Language::Pt => "áâãàçéêíóôõúüÁÂÃÀÇÉÊÍÓÔÕÚÜ".contains(ch),
+            Language::Pl => "ąćęłńóśźżĄĆĘŁŃÓŚŹŻ".contains(ch),
+            Language::Tr => "çğıöşüâîûÇĞİÖŞÜÂÎÛ".contains(ch),
+            Language::Ru => matches!(code, 0x0400..=0x052F),
+            Language::Nl => "áéíóúàèëïöüÁÉÍÓÚÀÈËÏÖÜ".contains(ch),
+            Language::Cs => "áčďéěíňóřšťúůýžÁČĎÉĚÍŇÓŘŠŤÚŮÝŽ".contains(ch),
+            Language::Ar => matches!(
+                code,
+                0x0600..=0x06FF | 0x0750..=0x077F | 0xFB50..=0xFDFF | 0xFE70..=0xFEFF
+            ),
+            Language::ZhCn => han,
+            Language::Ja => {
+                han || matches!(code, 0x3040..=0x30FF | 0x31F0..=0x31FF | 0xFF66..=0xFF9F)
+            }
+            Language::Hu => "áéíóöőúüűÁÉÍÓÖŐÚÜŰ".contains(ch),
+            Language::Ko => {
+                han || matches!(code, 0x1100..=0x11FF | 0x3130..=0x318F | 0xAC00..=0xD7AF)
+            }
+            Language::Hi => matches!(code, 0x0900..=0x097F),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+enum CharsetAction {
+    /// Report the letters and leave them in the text.
+    #[default]
+    Report,
+    /// Fold Latin diacritics and romanize Cyrillic and Greek; remove what has no reading.
+    Transliterate,
+    /// Fold Latin diacritics and remove the words still holding an unsupported letter.
+    Remove,
+}
+
+/// An unsupported letter, how often it occurs and where.
+#[derive(Debug)]
+pub(crate) struct UnsupportedLetter {
+    pub(crate) count: usize,
+    pub(crate) positions: Vec<LetterPosition>,
+}
+
+/// Where a letter was found: line and column (from 1) of the input file, or of a document
+/// in it (an EPUB chapter) when `document` names one.
+#[derive(Debug)]
+pub(crate) struct LetterPosition {
+    document: Option<String>,
+    line: usize,
+    column: usize,
+}
+
+/// Add the letters in `text` outside the target alphabet to `found`, with positions in
+/// `document`. Greek letters standing alone are skipped when `names_greek` is set, since
+/// `[math]` names them.
+pub(crate) fn scan(
+    text: &str,
+    document: Option<&str>,
+    cfg: &CharsetConfig,
+    names_greek: bool,
+    found: &mut BTreeMap<char, UnsupportedLetter>,
+) {
+    for (line_idx, line) in text.lines().enumerate() {
+        let chars: Vec<char> = line.chars().collect();
+        for (idx, &ch) in chars.iter().enumerate() {
+            if !cfg.is_unsupported(ch) {
+                continue;
+            }
+            let before = idx.checked_sub(1).map(|idx| chars[idx]);
+            let after = chars.get(idx + 1).copied();
+            if names_greek
+                && crate::math::greek_name(ch).is_some()
+                && !before.is_some_and(char::is_alphabetic)
+                && !after.is_some_and(char::is_alphabetic)
+            {
+                continue;
+            }
+            let letter = found.entry(ch).or_insert(UnsupportedLetter {
+                count: 0,
+                positions: Vec::new(),
+            });
+            letter.count += 1;
+            letter.positions.push(LetterPosition {
+                document: document.map(str::to_string),
+                line: line_idx + 1,
+                column: idx + 1,
+            });
+        }
+    }
+}
+
+/// Transliterate or remove the unsupported letters in `text` as `cfg.action` says; returns
+/// the text and how many letters were changed.
+pub(crate) fn apply(text: &str, cfg: &CharsetConfig) -> (String, usize) {
+    let mut count = 0;
+    let text = match cfg.action {
+        CharsetAction::Report => return (text.to_string(), 0),
+        CharsetAction::Transliterate => text
+            .chars()
+            .map(|ch| {
+                if !cfg.is_unsupported(ch) {
+                    return ch.to_string();
+                }
+                count += 1;
+                transliterate(ch).unwrap_or_default()
+            })
+            .collect(),
+        CharsetAction::Remove => text
+            .split('\n')
+            .map(|line| {
+                line.split(' ')
+                    .filter_map(|word| remove_word(word, cfg, &mut count))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    };
+    (text, count)
+}
+
+/// `word` with its Latin diacritics folded ("Håkon" is "Hakon"), or, if other unsupported
+/// letters remain, only the sentence punctuation after it ("Москва," leaves ",").
+fn remove_word(word: &str, cfg: &CharsetConfig, count: &mut usize) -> Option<String> {
+    let mut folded = String::with_capacity(word.len());
+    let mut unreadable = false;
+    for ch in word.chars() {
+        if !cfg.is_unsupported(ch) {
+            folded.push(ch);
+            continue;
+        }
+        *count += 1;
+        match fold_latin(ch) {
+            Some(reading) => folded.push_str(&reading),
+            None => unreadable = true,
+        }
+    }
+    if !unreadable {
+        return Some(folded);
+    }
+    let through_last_letter = word.trim_end_matches(|ch: char| !ch.is_alphanumeric());
+    let punctuation: String = word[through_last_letter.len()..]
+        .chars()
+        .filter(|ch| ".,;:!?".contains(*ch))
+        .collect();
+    (!punctuation.is_empty()).then_some(punctuation)
+}
+
+/// A Latin letter without its diacritics ("å" is "a", "ß" is "ss").
+fn fold_latin(ch: char) -> Option<String> {
+    if let Some((_, reading)) = LATIN_SPECIAL.iter().find(|(letter, _)| *letter == ch) {
+        return Some(reading.to_string());
+    }
+    let base: String = ch.nfd().filter(|ch| !is_combining_mark(*ch)).collect();
+    (!base.is_empty() && base.chars().all(|ch| ch.is_ascii_alphabetic())).then_some(base)
+}
+
+/// An ASCII reading of a letter: Latin letters lose their diacritics, Cyrillic and Greek
+/// letters are romanized. Capitals keep a capital first letter ("Ж" is "Zh").
+fn transliterate(ch: char) -> Option<String> {
+    if let Some(reading) = fold_latin(ch) {
+        return Some(reading);
+    }
+    let base: String = ch.nfd().filter(|ch| !is_combining_mark(*ch)).collect();
+    let mut letters = base.chars();
+    let letter = letters.next()?;
+    if letters.next().is_some() {
+        return None;
+    }
+    let small = letter.to_lowercase().next()?;
+    let reading = CYRILLIC
+        .iter()
+        .chain(GREEK)
+        .find(|(letter, _)| *letter == small)
+        .map(|(_, reading)| *reading)?;
+    if small == letter {
+        return Some(reading.to_string());
+    }
+    let mut chars = reading.chars();
+    Some(match chars.next() {
+        Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+        None => String::new(),
+    })
+}
+
+/// The target alphabet and what happens to letters outside it, for the log.
+pub(crate) fn describe(cfg: &CharsetConfig) -> String {
+    let action = match cfg.action {
+        CharsetAction::Report => "left in place",
+        CharsetAction::Transliterate => "transliterated",
+        CharsetAction::Remove => "folded or removed with their words",
+    };
+    format!("outside the {} alphabet, {action}", cfg.language.name())
+}
+
+/// Render unsupported letters as `å U+00E5 (1): 11:15 -> a` entries; positions in a
+/// document are prefixed with its name (`"Chapter One" 11:15`). In `remove` mode only the
+/// Latin letters that are folded get a reading.
+pub(crate) fn format_unsupported(
+    found: &BTreeMap<char, UnsupportedLetter>,
+    cfg: &CharsetConfig,
+    separator: &str,
+) -> String {
+    found
+        .iter()
+        .map(|(letter, found)| {
+            let mut positions = found
+                .positions
+                .iter()
+                .take(cfg.max_positions)
+                .map(|position| match &position.document {
+                    Some(document) => {
+                        format!("\"{document}\" {}:{}", position.line, position.column)
+                    }
+                    None => format!("{}:{}", position.line, position.column),
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            if found.positions.len() > cfg.max_positions {
+                positions.push_str(" ...");
+            }
+            let reading = match cfg.action {
+                CharsetAction::Remove => fold_latin(*letter),
+                _ => transliterate(*letter),
+            };
+            let reading = match reading {
+                Some(reading) if !reading.is_empty() => format!(" -> {reading}"),
+                _ => String::new(),
+            };
+            format!(
+                "{letter} U+{:04X} ({}): {positions}{reading}",
+                *letter as u32, found.count
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(separator)
+}
@@ -0,0 +1,109 @@
+//! Author–year citations.
+//!
+//! Parenthetical citations in the APA, Chicago and Harvard styles ("(Lie & Bos, 1999)",
+//! "(see Smith et al., 2020, p. 4; Jones 2018)", "(Lie 1994: 12)") are removed whole, and
+//! narrative ones keep the author in the sentence ("Meyer (2004) argued" reads "Meyer
+//! argued"). Authors may be organisations ("(W3C, 1996)"); product names followed by a year
+//! ("(Windows 2000)") are never taken for authors. Parentheticals that merely contain a number
+//! ("(about 3 weeks)", "(May 2010)") are left alone.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// A surname, with lowercase particles ("van Dijk", "de la Cruz") and apostrophes ("O'Neil"),
+/// or an organisation ("W3C", "IEEE").
+const NAME: &str =
+    r"(?:(?:van|von|de|der|den|du|da|di|la|le)\s+)*\p{Lu}[\p{L}\p{N}'’-]*[\p{L}\p{N}]";
+/// A publication year, optionally disambiguated ("1999b"), or its stand-ins.
+const YEAR: &str = r"(?:1[5-9]\d\d|20\d\d)[a-z]?|n\.\s?d\.|in press|forthcoming";
+/// A page, chapter or section locator after the year.
+const LOCATOR: &str = r"(?:,\s*|:\s*)(?:(?:pp?\.|ch\.|chap\.|sec\.|§)\s*)?\d+(?:\s*[-–]\s*\d+)?";
+/// "see", "e.g.," and friends before a citation.
+const PREFIX: &str = r"(?:(?:see(?:\s+also)?|e\.g\.,?|cf\.|i\.e\.,?|as\s+cited\s+in)\s+)?";
+
+/// Names that are followed by a year or version without being authors.
+const NOT_AUTHORS: &[&str] = &[
+    "Windows",
+    "Office",
+    "Word",
+    "Excel",
+    "Netscape",
+    "Navigator",
+    "Explorer",
+    "Firefox",
+    "Chrome",
+    "Safari",
+    "Opera",
+    "Edge",
+    "Mozilla",
+    "Mac",
+    "Ubuntu",
+    "Debian",
+    "Fedora",
+    "Android",
+    "Java",
+    "Python",
+    "Perl",
+    "Photoshop",
+    "Dreamweaver",
+    "Flash",
+    "Studio",
+    "Server",
+    "Level",
+    "Version",
+    "Release",
+    "Unicode",
+    "ECMAScript",
+    "Expo",
+    "Olympics",
+];
+
+static RE_PARENTHETICAL: Lazy<Regex> = Lazy::new(|| {
+    let authors = format!(r"{NAME}(?:\s+et\s+al\.?|(?:,\s*{NAME})*,?\s+(?:&|and)\s+{NAME})?");
+    let cite = format!(r"{PREFIX}{authors},?\s+(?:{YEAR})(?:,\s*(?:{YEAR}))*(?:{LOCATOR})?");
+    Regex::new(&format!(r"[ \t]*\(\s*{cite}(?:\s*;\s*{cite})*\s*\)")).unwrap()
+});
+/// The year in parentheses after a name or "et al." in running text.
+static RE_NARRATIVE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(
+        r"(?P<who>{NAME}|et\s+al\.)[ \t]*\(\s*(?:{YEAR})(?:,\s*(?:{YEAR}))*(?:{LOCATOR})?\s*\)"
+    ))
+    .unwrap()
+});
+static RE_FIRST_NAME: Lazy<Regex> = Lazy::new(|| Regex::new(r"\p{Lu}[\p{L}'’-]*").unwrap());
+
+/// A month or season before a year is a date, not an author.
+static RE_DATE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"\b(?:Jan(?:uary)?|Feb(?:ruary)?|Mar(?:ch)?|Apr(?:il)?|May|June?|July?|Aug(?:ust)?|Sep(?:t|tember)?|Oct(?:ober)?|Nov(?:ember)?|Dec(?:ember)?|Spring|Summer|Autumn|Fall|Winter)\.?,?\s+\d{4}",
+    )
+    .unwrap()
+});
+
+/// Remove parenthetical author–year citations and reduce narrative ones to the author.
+pub(crate) fn drop_author_year(text: &str) -> String {
+    let text = RE_PARENTHETICAL.replace_all(text, |caps: &regex::Captures| {
+        let cited = &caps[0];
+        if RE_DATE.is_match(cited) || names_product(cited) {
+            cited.to_string()
+        } else {
+            String::new()
+        }
+    });
+    RE_NARRATIVE
+        .replace_all(&text, |caps: &regex::Captures| {
+            if RE_DATE.is_match(&caps[0]) || names_product(&caps["who"]) {
+                caps[0].to_string()
+            } else {
+                caps["who"].to_string()
+            }
+        })
+        .to_string()
+}
+
+/// Whether the first capitalized word of `text` is a product or event name, not an author.
+fn names_product(text: &str) -> bool {
+    RE_FIRST_NAME
+        .find(text)
+        .is_some_and(|name| NOT_AUTHORS.contains(&name.as_str()))
+}
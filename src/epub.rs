@@ -0,0 +1,385 @@
+//! EPUB input.
+//!
+//! The OPF package named by `META-INF/container.xml` gives the reading order (the spine) and
+//! the table of contents (the EPUB 3 nav document or the EPUB 2 NCX) gives chapter titles.
+//! Each spine document is converted like HTML input and becomes one chapter; covers,
+//! copyright pages, indexes and other front or back matter are skipped by `[epub]` rules.
+
+use anyhow::{Context, Result, bail};
+use log::{debug, info};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use roxmltree::{Document, Node};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use zip::ZipArchive;
+
+use crate::chapters::Chapter;
+use crate::html::{self, HtmlConfig};
+
+/// `epub:type` values on the body or its first sections.
+static RE_EPUB_TYPE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"epub:type\s*=\s*["']([^"']+)["']"#).unwrap());
+static RE_BODY: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)<body\b").unwrap());
+
+/// `[epub]`: EPUB input (`io.input_format = "epub"`).
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub(crate) struct EpubConfig {
+    /// Write one file per spine document, as with `chapters.split_output`.
+    split_output: bool,
+    /// Skip spine items marked `linear="no"` (pop-up notes, answer keys).
+    skip_non_linear: bool,
+    /// Skip documents whose `epub:type` or OPF guide type is one of these.
+    skip_types: Vec<String>,
+    /// Skip documents whose TOC title or file name starts with one of these words.
+    skip_names: Vec<String>,
+}
+
+impl Default for EpubConfig {
+    fn default() -> Self {
+        Self {
+            split_output: true,
+            skip_non_linear: true,
+            skip_types: [
+                "cover",
+                "titlepage",
+                "title-page",
+                "copyright-page",
+                "toc",
+                "index",
+                "loi",
+                "lot",
+            ]
+            .map(str::to_string)
+            .to_vec(),
+            skip_names: [
+                "cover",
+                "copyright",
+                "index",
+                "contents",
+                "table of contents",
+            ]
+            .map(str::to_string)
+            .to_vec(),
+        }
+    }
+}
+
+impl EpubConfig {
+    pub(crate) fn split_output(&self) -> bool {
+        self.split_output
+    }
+
+    fn skips_type(&self, types: &[String]) -> bool {
+        types.iter().any(|found| {
+            self.skip_types
+                .iter()
+                .any(|skipped| skipped.eq_ignore_ascii_case(found))
+        })
+    }
+
+    /// Whole words only: "Index" and "cover-page" match, "Discovery" does not.
+    fn skips_name(&self, name: &str) -> bool {
+        let name = name.trim().to_lowercase();
+        self.skip_names.iter().any(|skipped| {
+            let skipped = skipped.to_lowercase();
+            name.strip_prefix(&skipped)
+                .is_some_and(|rest| !rest.starts_with(char::is_alphanumeric))
+        })
+    }
+}
+
+/// A spine document to read.
+struct SpineItem {
+    href: String,
+    linear: bool,
+}
+
+/// Read the chapters of the EPUB in `bytes`, in spine order.
+pub(crate) fn read(bytes: &[u8], cfg: &EpubConfig, html_cfg: &HtmlConfig) -> Result<Vec<Chapter>> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes)).context("not a valid EPUB (zip) file")?;
+
+    let container = read_entry(&mut archive, "META-INF/container.xml")?;
+    let container = Document::parse(&container).context("parsing META-INF/container.xml")?;
+    let Some(opf_path) = container
+        .descendants()
+        .find(|node| node.has_tag_name("rootfile"))
+        .and_then(|node| node.attribute("full-path"))
+        .map(str::to_string)
+    else {
+        bail!("META-INF/container.xml names no OPF package");
+    };
+    let opf_text = read_entry(&mut archive, &opf_path)?;
+    let opf = Document::parse(&opf_text).with_context(|| format!("parsing {opf_path}"))?;
+    let base = parent_dir(&opf_path);
+
+    // Manifest: id -> (href, media type, properties).
+    let mut manifest: HashMap<&str, (String, &str, &str)> = HashMap::new();
+    for item in opf.descendants().filter(|node| node.has_tag_name("item")) {
+        if let (Some(id), Some(href)) = (item.attribute("id"), item.attribute("href")) {
+            manifest.insert(
+                id,
+                (
+                    resolve(&base, href),
+                    item.attribute("media-type").unwrap_or_default(),
+                    item.attribute("properties").unwrap_or_default(),
+                ),
+            );
+        }
+    }
+    let spine_node = opf.descendants().find(|node| node.has_tag_name("spine"));
+    let spine: Vec<SpineItem> = spine_node
+        .iter()
+        .flat_map(|spine| spine.children().filter(|node| node.has_tag_name("itemref")))
+        .filter_map(|itemref| {
+            let (href, media_type, _) = manifest.get(itemref.attribute("idref")?)?;
+            media_type.contains("html").then(|| SpineItem {
+                href: href.clone(),
+                linear: itemref.attribute("linear") != Some("no"),
+            })
+        })
+        .collect();
+    if spine.is_empty() {
+        bail!("{opf_path} has an empty spine");
+    }
+
+    // Guide references (EPUB 2) type documents as cover, toc, copyright-page, ...
+    let mut guide_types: HashMap<String, Vec<String>> = HashMap::new();
+    for reference in opf
+        .descendants()
+        .filter(|node| node.has_tag_name("reference"))
+    {
+        if let (Some(kind), Some(href)) = (reference.attribute("type"), reference.attribute("href"))
+        {
+            guide_types
+                .entry(strip_fragment(&resolve(&base, href)))
+                .or_default()
+                .push(kind.to_string());
+        }
+    }
+
+    let nav = manifest
+        .values()
+        .find(|(_, _, properties)| properties.split_whitespace().any(|p| p == "nav"))
+        .map(|(href, _, _)| href.clone());
+    let ncx = spine_node
+        .and_then(|spine| spine.attribute("toc"))
+        .and_then(|id| manifest.get(id))
+        .map(|(href, _, _)| href.clone());
+    let titles = toc_titles(&mut archive, nav.as_deref(), ncx.as_deref());
+    if let Some(title) = opf
+        .descendants()
+        .find(|node| node.tag_name().name() == "title")
+        .and_then(|node| node.text())
+    {
+        info!("Reading EPUB \"{}\"", title.trim());
+    }
+
+    let mut chapters = Vec::new();
+    let mut skipped = Vec::new();
+    for item in &spine {
+        let xhtml = read_entry(&mut archive, &item.href)?;
+        let title = titles.get(&item.href).cloned();
+        let mut types = guide_types.get(&item.href).cloned().unwrap_or_default();
+        types.extend(epub_types(&xhtml));
+        if nav.as_deref() == Some(item.href.as_str()) {
+            types.push("toc".to_string());
+        }
+        let name = file_stem(&item.href);
+        if (cfg.skip_non_linear && !item.linear)
+            || cfg.skips_type(&types)
+            || title.as_deref().is_some_and(|title| cfg.skips_name(title))
+            || cfg.skips_name(name)
+        {
+            skipped.push(item.href.clone());
+            continue;
+        }
+        let mut text = html::to_text(&xhtml, html_cfg);
+        if text.trim().is_empty() {
+            debug!("Skipping {} (no text)", item.href);
+            continue;
+        }
+        // Chapters whose document has no heading of its own get the TOC title.
+        if let Some(title) = &title
+            && !text.trim_start().starts_with('#')
+        {
+            text = format!("# {title}\n\n{text}");
+        }
+        chapters.push(Chapter { title, text });
+    }
+    if chapters.is_empty() {
+        bail!("every spine document was skipped or empty");
+    }
+    info!(
+        "Read {} of {} spine documents{}",
+        chapters.len(),
+        spine.len(),
+        if skipped.is_empty() {
+            String::new()
+        } else {
+            format!(", skipped {}", skipped.join(", "))
+        }
+    );
+    Ok(chapters)
+}
+
+fn read_entry(archive: &mut ZipArchive<Cursor<&[u8]>>, path: &str) -> Result<String> {
+    let mut entry = archive
+        .by_name(path)
+        .with_context(|| format!("EPUB has no {path}"))?;
+    let mut text = String::new();
+    entry
+        .read_to_string(&mut text)
+        .with_context(|| format!("reading {path} from the EPUB"))?;
+    Ok(text)
+}
+
+/// Chapter titles keyed by document path, from the nav document or else the NCX.
+fn toc_titles(
+    archive: &mut ZipArchive<Cursor<&[u8]>>,
+    nav: Option<&str>,
+    ncx: Option<&str>,
+) -> HashMap<String, String> {
+    let mut titles = HashMap::new();
+    for (path, is_nav) in [(nav, true), (ncx, false)] {
+        let Some(path) = path else {
+            continue;
+        };
+        let Ok(text) = read_entry(archive, path) else {
+            continue;
+        };
+        // XHTML may use HTML entities XML does not know; decode them first.
+        let text = text.replace("&nbsp;", "&#160;");
+        let Ok(doc) = Document::parse(&text) else {
+            debug!("Could not parse table of contents {path}");
+            continue;
+        };
+        let base = parent_dir(path);
+        let mut add = |href: &str, title: String| {
+            let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+            if !title.is_empty() {
+                titles
+                    .entry(strip_fragment(&resolve(&base, href)))
+                    .or_insert(title);
+            }
+        };
+        if is_nav {
+            let toc = doc
+                .descendants()
+                .filter(|node| node.has_tag_name("nav"))
+                .find(|node| {
+                    node.attributes()
+                        .any(|attr| attr.name() == "type" && attr.value().contains("toc"))
+                });
+            for link in toc
+                .iter()
+                .flat_map(|toc| toc.descendants())
+                .filter(|node| node.has_tag_name("a"))
+            {
+                if let Some(href) = link.attribute("href") {
+                    add(href, text_content(link));
+                }
+            }
+        } else {
+            for point in doc
+                .descendants()
+                .filter(|node| node.has_tag_name("navPoint"))
+            {
+                let label = point
+                    .children()
+                    .find(|node| node.has_tag_name("navLabel"))
+                    .map(text_content);
+                let src = point
+                    .children()
+                    .find(|node| node.has_tag_name("content"))
+                    .and_then(|node| node.attribute("src"));
+                if let (Some(label), Some(src)) = (label, src) {
+                    add(src, label);
+                }
+            }
+        }
+        if !titles.is_empty() {
+            break;
+        }
+    }
+    titles
+}
+
+fn text_content(node: Node) -> String {
+    node.descendants()
+        .filter(|node| node.is_text())
+        .filter_map(|node| node.text())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `epub:type` values near the top of a content document.
+fn epub_types(xhtml: &str) -> Vec<String> {
+    let start = RE_BODY.find(xhtml).map_or(0, |body| body.start());
+    let head: String = xhtml[start..].chars().take(2000).collect();
+    RE_EPUB_TYPE
+        .captures_iter(&head)
+        .flat_map(|caps| {
+            caps[1]
+                .split_whitespace()
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn parent_dir(path: &str) -> String {
+    path.rsplit_once('/')
+        .map_or_else(String::new, |(dir, _)| format!("{dir}/"))
+}
+
+fn file_stem(path: &str) -> &str {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    name.split('.').next().unwrap_or(name)
+}
+
+fn strip_fragment(href: &str) -> String {
+    href.split('#').next().unwrap_or(href).to_string()
+}
+
+/// Resolve `href` (percent-encoded, possibly with `..`) against the directory `base`.
+fn resolve(base: &str, href: &str) -> String {
+    let path = format!("{base}{}", percent_decode(href));
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let hex = (bytes[idx] == b'%')
+            .then(|| text.get(idx + 1..idx + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match hex {
+            Some(byte) => {
+                out.push(byte);
+                idx += 3;
+            }
+            None => {
+                out.push(bytes[idx]);
+                idx += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
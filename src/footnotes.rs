@@ -0,0 +1,398 @@
+//! Footnotes and endnotes.
+//!
+//! Markdown footnotes (`[^1]` / `[^1]: ...`) and notes marked with Unicode superscripts
+//! ("as Lie argued.¹" / "¹ See Lie, 1994, p. 12.") are found on the whole document, before
+//! chapters are split, since Markdown collects every definition at the end. Note bodies are
+//! taken out and each reference becomes a private-use marker; once the chapters are known the
+//! notes are dropped, read after the paragraph citing them, or collected at the chapter end.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::chapters::{self, ChapterConfig};
+
+/// Opens a note reference; the note index follows as ASCII digits.
+const REF_OPEN: char = '\u{E020}';
+/// Closes a note reference.
+const REF_CLOSE: char = '\u{E021}';
+
+const SUPERSCRIPT_DIGITS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+
+/// A Markdown footnote definition: `[^id]: text`.
+static RE_MARKDOWN_DEFINITION: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s{0,3}\[\^(?P<id>[\p{L}\p{N}_-]+)\]:\s*(?P<body>.*)$").unwrap());
+/// A Markdown footnote reference: `[^id]`.
+static RE_MARKDOWN_REF: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\[\^(?P<id>[\p{L}\p{N}_-]+)\]").unwrap());
+/// A superscript number attached to the word, punctuation or number it annotates; after a
+/// lone letter it is an exponent ("m²", "x³"), and after a number only in the places
+/// `is_number_reference` allows.
+static RE_SUPERSCRIPT_REF: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?P<before>\p{L}{2}|\p{P}|\d)(?P<id>[⁰¹²³⁴-⁹]+)").unwrap());
+/// A note body line: `¹ See ...`, `1. See ...`, `1) See ...`.
+static RE_NOTE_BODY: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*(?:(?P<sup>[⁰¹²³⁴-⁹]+)|(?P<num>\d{1,3})[.)]?)\s+(?P<body>\S.*)$").unwrap()
+});
+/// A bracketed number, as numeric references are written: `[12]`.
+static RE_BRACKET_REF: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[(?P<id>\d{1,3})\]").unwrap());
+/// A Markdown heading of any level; notes close the section above it.
+static RE_SECTION_HEADING: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s{0,3}#{1,6}[ \t]").unwrap());
+static RE_REF: Lazy<Regex> = Lazy::new(|| Regex::new(r"[ \t]*\u{E020}(\d+)\u{E021}").unwrap());
+
+/// `[footnotes]`: footnote and endnote handling.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub(crate) struct FootnoteConfig {
+    /// Recognize Markdown and superscript footnotes.
+    detect: bool,
+    /// What happens to the note bodies; reference markers are always removed.
+    mode: FootnoteMode,
+    /// Word introducing a note that is read aloud ("Footnote: ...", "Footnote 2: ...").
+    label: String,
+    /// Heading read before the notes collected at the end of a chapter (empty for none).
+    end_heading: String,
+}
+
+impl Default for FootnoteConfig {
+    fn default() -> Self {
+        Self {
+            detect: true,
+            mode: FootnoteMode::Drop,
+            label: "Footnote".to_string(),
+            end_heading: "Notes".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+enum FootnoteMode {
+    /// Remove the notes.
+    #[default]
+    Drop,
+    /// Read each note after the paragraph that references it ("Footnote: ...").
+    Inline,
+    /// Collect the notes at the end of the chapter, numbered in reading order.
+    EndOfChapter,
+}
+
+/// How a plain note is numbered: `¹` or `1.` / `[1]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Marker {
+    Superscript,
+    Numeral,
+}
+
+/// Note bodies taken out of the document, indexed by the reference markers left behind.
+#[derive(Debug, Default)]
+pub(crate) struct Footnotes {
+    bodies: Vec<String>,
+}
+
+impl Footnotes {
+    pub(crate) fn len(&self) -> usize {
+        self.bodies.len()
+    }
+}
+
+/// Take note bodies out of `text` and turn their references into markers.
+///
+/// Markdown definitions are taken wherever they are. Plain bodies ("¹ See ...", "1. See ...")
+/// only count in the block of notes that closes a section or chapter, and only when a
+/// superscript or `[n]` reference with their number comes before them; a superscript
+/// reference prefers a superscript body, so an ordered list above the notes stays. A
+/// superscript after a word without a body stays as it is ("cm²"); one after punctuation or
+/// a number and Markdown references without a definition are dropped.
+pub(crate) fn extract(
+    text: &str,
+    cfg: &FootnoteConfig,
+    chapters: &ChapterConfig,
+) -> (String, Footnotes) {
+    let mut notes = Footnotes::default();
+    if !cfg.detect {
+        return (text.to_string(), notes);
+    }
+    let lines: Vec<&str> = text.lines().collect();
+    let mut removed = vec![false; lines.len()];
+    let mut markdown: HashMap<String, usize> = HashMap::new();
+
+    let mut references: Vec<(usize, String, Marker)> = Vec::new();
+    for (idx, line) in lines.iter().enumerate() {
+        for caps in RE_SUPERSCRIPT_REF.captures_iter(line) {
+            if is_superscript_ref(line, &caps) {
+                references.push((idx, ascii_digits(&caps["id"]), Marker::Superscript));
+            }
+        }
+        for caps in RE_BRACKET_REF.captures_iter(line) {
+            if is_bracket_ref(line, &caps) {
+                references.push((idx, caps["id"].to_string(), Marker::Numeral));
+            }
+        }
+    }
+    let in_notes = trailing_note_blocks(&lines, chapters);
+    let candidates: Vec<(usize, String, Marker)> = (0..lines.len())
+        .filter(|&idx| in_notes[idx])
+        .filter_map(|idx| {
+            let caps = RE_NOTE_BODY.captures(lines[idx])?;
+            Some(match caps.name("sup") {
+                Some(sup) => (idx, ascii_digits(sup.as_str()), Marker::Superscript),
+                None => (idx, caps["num"].to_string(), Marker::Numeral),
+            })
+        })
+        .collect();
+
+    // Each claimed plain body answers the references with its number since the previous
+    // body with that number, so chapters can restart their numbering.
+    let mut claimed: Vec<(usize, String, usize)> = Vec::new();
+    let mut idx = 0;
+    while idx < lines.len() {
+        let line = lines[idx];
+        let (first, is_markdown) = if let Some(caps) = RE_MARKDOWN_DEFINITION.captures(line) {
+            markdown.insert(caps["id"].to_string(), notes.bodies.len());
+            (caps["body"].to_string(), true)
+        } else if let Some((_, id, marker)) = candidates.iter().find(|(at, _, _)| *at == idx) {
+            let since = claimed
+                .iter()
+                .rev()
+                .find(|(_, claimed_id, _)| claimed_id == id)
+                .map_or(0, |(at, _, _)| at + 1);
+            let cited: Vec<Marker> = references
+                .iter()
+                .filter(|(at, ref_id, _)| ref_id == id && (since..idx).contains(at))
+                .map(|(_, _, marker)| *marker)
+                .collect();
+            let prefers_other = *marker == Marker::Numeral
+                && !cited.contains(&Marker::Numeral)
+                && candidates.iter().any(|(at, other, other_marker)| {
+                    *at > since && other == id && *other_marker == Marker::Superscript
+                });
+            if cited.is_empty() || prefers_other {
+                idx += 1;
+                continue;
+            }
+            claimed.push((idx, id.clone(), notes.bodies.len()));
+            (RE_NOTE_BODY.replace(line, "$body").to_string(), false)
+        } else {
+            idx += 1;
+            continue;
+        };
+
+        let mut body = vec![first.trim().to_string()];
+        removed[idx] = true;
+        idx += 1;
+        // Markdown continues a definition with indented lines; a plain note runs to the next
+        // blank line or note.
+        while let Some(next) = lines.get(idx) {
+            let continues = if is_markdown {
+                next.starts_with([' ', '\t']) && !next.trim().is_empty()
+            } else {
+                !next.trim().is_empty()
+                    && !RE_NOTE_BODY.is_match(next)
+                    && !RE_MARKDOWN_DEFINITION.is_match(next)
+            };
+            if !continues {
+                break;
+            }
+            body.push(next.trim().to_string());
+            removed[idx] = true;
+            idx += 1;
+        }
+        notes.bodies.push(body.join(" "));
+    }
+
+    // The body a plain reference on line `idx` points to: the next claimed one with its id.
+    let body_for = |idx: usize, id: &str| {
+        claimed
+            .iter()
+            .find(|(at, claimed_id, _)| *at > idx && claimed_id == id)
+            .map(|(_, _, note)| *note)
+    };
+    let kept: Vec<String> = lines
+        .iter()
+        .enumerate()
+        .filter(|(idx, _)| !removed[*idx])
+        .map(|(idx, line)| {
+            let line = RE_MARKDOWN_REF.replace_all(line, |caps: &regex::Captures| {
+                markdown
+                    .get(&caps["id"])
+                    .map(|&note| reference(note))
+                    .unwrap_or_default()
+            });
+            let line = RE_BRACKET_REF.replace_all(&line, |caps: &regex::Captures| {
+                match body_for(idx, &caps["id"]) {
+                    Some(note) if is_bracket_ref(&line, caps) => reference(note),
+                    _ => caps[0].to_string(),
+                }
+            });
+            RE_SUPERSCRIPT_REF
+                .replace_all(&line, |caps: &regex::Captures| {
+                    if !is_superscript_ref(&line, caps) {
+                        return caps[0].to_string();
+                    }
+                    match body_for(idx, &ascii_digits(&caps["id"])) {
+                        Some(note) => format!("{}{}", &caps["before"], reference(note)),
+                        // A superscript after punctuation or a number is a reference whose
+                        // note is lost.
+                        None if caps["before"].starts_with(char::is_alphabetic) => {
+                            caps[0].to_string()
+                        }
+                        None => caps["before"].to_string(),
+                    }
+                })
+                .to_string()
+        })
+        .collect();
+    (kept.join("\n"), notes)
+}
+
+/// Whether a superscript match is a reference rather than an exponent on a unit ("cm²",
+/// "km³") or a number.
+fn is_superscript_ref(line: &str, caps: &regex::Captures) -> bool {
+    let whole = caps.get(0).unwrap();
+    let id = caps.name("id").unwrap();
+    let before = &caps["before"];
+    if before.starts_with(|ch: char| ch.is_ascii_digit()) {
+        return is_number_reference(line, id.start(), id.end());
+    }
+    let unit = before.chars().all(char::is_alphabetic)
+        && !line[..whole.start()]
+            .chars()
+            .next_back()
+            .is_some_and(char::is_alphabetic);
+    !(unit && matches!(id.as_str(), "²" | "³"))
+}
+
+/// Whether the superscript at `line[start..end]`, after a number, is a footnote reference:
+/// after a 4-digit number ("in 1994¹"), or before sentence punctuation or the end of the
+/// line ("Table 2³."). Elsewhere, and on an operand ("x = 3² + 4²."), it is an exponent.
+pub(crate) fn is_number_reference(line: &str, start: usize, end: usize) -> bool {
+    let number = line[..start].trim_end_matches(|ch: char| ch.is_ascii_digit());
+    let digits = start - number.len();
+    if number
+        .trim_end_matches([' ', '\t'])
+        .ends_with(['+', '-', '−', '±', '×', '·', '*', '/', '=', '<', '>', '^'])
+    {
+        return false;
+    }
+    let after = line[end..].trim_start_matches([' ', '\t']);
+    digits == 4 || after.is_empty() || after.starts_with(['.', ',', ';', ':', '!', '?', ')'])
+}
+
+/// Which lines belong to the block of paragraphs that closes a section: paragraphs starting
+/// with a note body, up to the next heading or the end of the text. Markdown definitions
+/// among them (`[^a]: ...`) are taken on their own and do not end the block.
+fn trailing_note_blocks(lines: &[&str], chapters: &ChapterConfig) -> Vec<bool> {
+    let mut in_notes = vec![false; lines.len()];
+    let is_break = |idx: usize| {
+        RE_SECTION_HEADING.is_match(lines[idx]) || chapters::is_heading(lines, idx, chapters)
+    };
+    let mut end = lines.len();
+    while end > 0 {
+        let start = (0..end)
+            .rev()
+            .find(|&idx| is_break(idx))
+            .map_or(0, |idx| idx + 1);
+        // Walk the section's paragraphs backwards while they open with a note body.
+        let mut cursor = end;
+        loop {
+            while cursor > start && lines[cursor - 1].trim().is_empty() {
+                cursor -= 1;
+            }
+            if cursor == start {
+                break;
+            }
+            let first = (start..cursor)
+                .rev()
+                .find(|&idx| lines[idx].trim().is_empty())
+                .map_or(start, |idx| idx + 1);
+            if RE_MARKDOWN_DEFINITION.is_match(lines[first]) {
+                cursor = first;
+                continue;
+            }
+            if !RE_NOTE_BODY.is_match(lines[first]) {
+                break;
+            }
+            in_notes[first..cursor]
+                .iter_mut()
+                .for_each(|line| *line = true);
+            cursor = first;
+        }
+        end = start.saturating_sub(1);
+    }
+    in_notes
+}
+
+/// Whether a `[n]` match is a reference rather than the text of a Markdown link (`[1](url)`).
+fn is_bracket_ref(line: &str, caps: &regex::Captures) -> bool {
+    let end = caps.get(0).map_or(0, |whole| whole.end());
+    !line[end..].starts_with('(')
+}
+
+/// Remove the reference markers from one chapter and place the notes they point to.
+pub(crate) fn place(text: &str, notes: &Footnotes, cfg: &FootnoteConfig) -> String {
+    if !text.contains(REF_OPEN) {
+        return text.to_string();
+    }
+    let mut seen = HashSet::new();
+    let mut pending = Vec::new();
+    let mut collected = Vec::new();
+    let mut out: Vec<String> = Vec::new();
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            flush_inline(&mut out, &mut pending, cfg);
+            out.push(String::new());
+            continue;
+        }
+        let line = RE_REF.replace_all(line, |caps: &regex::Captures| {
+            let note: usize = caps[1].parse().unwrap_or(usize::MAX);
+            if let Some(body) = notes.bodies.get(note)
+                && seen.insert(note)
+            {
+                match cfg.mode {
+                    FootnoteMode::Drop => {}
+                    FootnoteMode::Inline => pending.push(body.as_str()),
+                    FootnoteMode::EndOfChapter => collected.push(body.as_str()),
+                }
+            }
+            String::new()
+        });
+        out.push(line.to_string());
+    }
+    flush_inline(&mut out, &mut pending, cfg);
+
+    if !collected.is_empty() {
+        out.push(String::new());
+        if !cfg.end_heading.trim().is_empty() {
+            out.push(format!("# {}", cfg.end_heading.trim()));
+            out.push(String::new());
+        }
+        for (number, body) in collected.iter().enumerate() {
+            out.push(format!("{} {}: {}", cfg.label, number + 1, body));
+            out.push(String::new());
+        }
+    }
+    out.join("\n")
+}
+
+/// Read the notes referenced by the paragraph just finished, each as its own paragraph.
+fn flush_inline(out: &mut Vec<String>, pending: &mut Vec<&str>, cfg: &FootnoteConfig) {
+    for body in pending.drain(..) {
+        out.push(String::new());
+        out.push(format!("{}: {}", cfg.label, body));
+    }
+}
+
+fn reference(note: usize) -> String {
+    format!("{REF_OPEN}{note}{REF_CLOSE}")
+}
+
+fn ascii_digits(superscript: &str) -> String {
+    superscript
+        .chars()
+        .filter_map(|ch| SUPERSCRIPT_DIGITS.iter().position(|&digit| digit == ch))
+        .map(|digit| char::from(b'0' + digit as u8))
+        .collect()
+}
@@ -0,0 +1,276 @@
+//! HTML input.
+//!
+//! The document is tokenized rather than pattern-matched: markup is dropped, `<script>`,
+//! `<style>`, `<nav>` and the other `skip_elements` are removed with their content, block
+//! elements become paragraph breaks, headings become Markdown `#` headings and list items
+//! `- ` bullets, so the rest of the pipeline treats them as in plain text. Entities are decoded
+//! last, which turns an escaped `&lt;div&gt;` into the literal `<div>` that
+//! `pronunciation.html_tag_pronunciation` still speaks.
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+/// Elements that end a paragraph.
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "header",
+    "hr",
+    "main",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "summary",
+    "table",
+    "tr",
+    "ul",
+];
+/// Elements whose content is not markup and runs to their end tag.
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style", "textarea", "title"];
+
+/// `[html]`: HTML input (`io.input_format = "html"`).
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub(crate) struct HtmlConfig {
+    /// Elements removed together with their content.
+    skip_elements: Vec<String>,
+}
+
+impl Default for HtmlConfig {
+    fn default() -> Self {
+        Self {
+            skip_elements: [
+                "script", "style", "nav", "head", "noscript", "template", "svg", "iframe",
+            ]
+            .map(str::to_string)
+            .to_vec(),
+        }
+    }
+}
+
+impl HtmlConfig {
+    fn skips(&self, name: &str) -> bool {
+        self.skip_elements
+            .iter()
+            .any(|skipped| skipped.eq_ignore_ascii_case(name))
+    }
+}
+
+/// A tag as written: its lowercase name and whether it closes an element.
+struct Tag {
+    name: String,
+    closing: bool,
+    self_closing: bool,
+}
+
+/// Convert an HTML document into plain text with blank lines between paragraphs.
+pub(crate) fn to_text(html: &str, cfg: &HtmlConfig) -> String {
+    let mut out = String::with_capacity(html.len() / 2);
+    let mut skipping: Vec<String> = Vec::new();
+    let mut preformatted = 0usize;
+    let mut rest = html;
+    while !rest.is_empty() {
+        let Some(open) = rest.find('<') else {
+            push_text(&mut out, rest, preformatted > 0, !skipping.is_empty());
+            break;
+        };
+        push_text(
+            &mut out,
+            &rest[..open],
+            preformatted > 0,
+            !skipping.is_empty(),
+        );
+        rest = &rest[open..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        // Doctype, CDATA and processing instructions carry no text.
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+            continue;
+        }
+        let Some((tag, len)) = parse_tag(rest) else {
+            // A "<" that starts no tag ("a < b") is text.
+            push_text(&mut out, "<", preformatted > 0, !skipping.is_empty());
+            rest = &rest[1..];
+            continue;
+        };
+        rest = &rest[len..];
+
+        if !tag.closing && !tag.self_closing && RAW_TEXT_ELEMENTS.contains(&tag.name.as_str()) {
+            let content_end = find_end_tag(rest, &tag.name);
+            if !cfg.skips(&tag.name) && skipping.is_empty() {
+                push_text(&mut out, &rest[..content_end], false, false);
+            }
+            rest = &rest[content_end..];
+            rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+            continue;
+        }
+        if cfg.skips(&tag.name) {
+            if tag.closing {
+                if let Some(pos) = skipping.iter().rposition(|name| *name == tag.name) {
+                    skipping.truncate(pos);
+                }
+            } else if !tag.self_closing {
+                skipping.push(tag.name);
+            }
+            continue;
+        }
+        if !skipping.is_empty() {
+            continue;
+        }
+        match tag.name.as_str() {
+            "br" => out.push('\n'),
+            "pre" => {
+                break_paragraph(&mut out);
+                if tag.closing {
+                    preformatted = preformatted.saturating_sub(1);
+                } else {
+                    preformatted += 1;
+                }
+            }
+            "li" if !tag.closing => {
+                trim_line_end(&mut out);
+                if !out.is_empty() && !out.ends_with('\n') {
+                    out.push('\n');
+                }
+                out.push_str("- ");
+            }
+            // An item left without text drops its bullet.
+            "li" if at_bare_bullet(&out) => {
+                out.truncate(out.len() - 2);
+                trim_line_end(&mut out);
+            }
+            "li" => {}
+            "td" | "th" => out.push(' '),
+            name if heading_level(name).is_some() => {
+                break_paragraph(&mut out);
+                if !tag.closing {
+                    out.push_str(&"#".repeat(heading_level(name).unwrap_or(1)));
+                    out.push(' ');
+                }
+            }
+            name if BLOCK_ELEMENTS.contains(&name) => break_paragraph(&mut out),
+            _ => {}
+        }
+    }
+    let decoded = html_escape::decode_html_entities(&out).to_string();
+    decoded
+        .lines()
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// Parse the tag at the start of `text`, returning it and its length in bytes.
+fn parse_tag(text: &str) -> Option<(Tag, usize)> {
+    let inner = &text[1..];
+    let (closing, inner) = match inner.strip_prefix('/') {
+        Some(inner) => (true, inner),
+        None => (false, inner),
+    };
+    let name_len = inner
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == ':'))
+        .unwrap_or(inner.len());
+    if name_len == 0 || !inner.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    // Find the closing ">" outside quoted attribute values.
+    let mut quote = None;
+    let mut end = None;
+    for (idx, ch) in inner[name_len..].char_indices() {
+        match (quote, ch) {
+            (Some(open), ch) if ch == open => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(ch),
+            (None, '>') => {
+                end = Some(name_len + idx);
+                break;
+            }
+            (None, '<') => return None,
+            _ => {}
+        }
+    }
+    let end = end?;
+    let tag = Tag {
+        name: inner[..name_len].to_ascii_lowercase(),
+        closing,
+        self_closing: inner[..end].trim_end().ends_with('/'),
+    };
+    let consumed = text.len() - inner.len() + end + 1;
+    Some((tag, consumed))
+}
+
+/// Byte offset of `</name` in `text` (case-insensitive), or the end of the text.
+fn find_end_tag(text: &str, name: &str) -> usize {
+    let needle = format!("</{name}");
+    text.to_ascii_lowercase()
+        .find(&needle)
+        .unwrap_or(text.len())
+}
+
+fn heading_level(name: &str) -> Option<usize> {
+    let level = name.strip_prefix('h')?.parse::<usize>().ok()?;
+    (1..=6).contains(&level).then_some(level)
+}
+
+/// Append a text node: whitespace collapses to single spaces except inside `<pre>`.
+fn push_text(out: &mut String, text: &str, preformatted: bool, skipping: bool) {
+    if skipping || text.is_empty() {
+        return;
+    }
+    if preformatted {
+        out.push_str(text);
+        return;
+    }
+    let starts_with_space = text.starts_with(char::is_whitespace);
+    let ends_with_space = text.ends_with(char::is_whitespace);
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if starts_with_space && !out.is_empty() && !out.ends_with([' ', '\n']) {
+        out.push(' ');
+    }
+    out.push_str(&words.join(" "));
+    if ends_with_space && !words.is_empty() {
+        out.push(' ');
+    }
+}
+
+fn trim_line_end(out: &mut String) {
+    let trimmed = out.trim_end_matches([' ', '\t']).len();
+    out.truncate(trimmed);
+}
+
+/// Whether the current line is a list bullet still waiting for its text.
+fn at_bare_bullet(out: &str) -> bool {
+    out.rsplit('\n').next() == Some("- ")
+}
+
+/// End the current paragraph with a blank line; a block inside a list item (`<li><p>`)
+/// keeps its text on the bullet's line.
+fn break_paragraph(out: &mut String) {
+    if at_bare_bullet(out) {
+        return;
+    }
+    trim_line_end(out);
+    if out.is_empty() || out.ends_with("\n\n") {
+        return;
+    }
+    out.push_str(if out.ends_with('\n') { "\n" } else { "\n\n" });
+}
@@ -0,0 +1,130 @@
+//! Scholarly and product identifiers.
+//!
+//! DOIs, ISBNs, ISSNs, arXiv IDs and PubMed IDs are meaningless aloud and, left alone, the
+//! version and slash stages turn them into long runs of "point" and "or". Each type is
+//! recognized with its own pattern and handled by its `[identifiers.<type>]` policy.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+static RE_DOI: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)(?:\bdoi:\s*|https?://(?:dx\.)?doi\.org/)?\b(?P<id>10\.\d{4,9}/[^\s<>]+)")
+        .unwrap()
+});
+static RE_ISBN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)\bISBN(?:-1[03])?:?\s*(?P<id>(?:97[89][- ]?)?\d{1,5}[- ]?\d{1,7}[- ]?\d{1,7}[- ]?[\dX])\b|\b(?P<bare>97[89]-\d{1,5}-\d{1,7}-\d{1,7}-\d)\b",
+    )
+    .unwrap()
+});
+static RE_ISSN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\bISSN:?\s*(?P<id>\d{4}-\d{3}[\dX])\b").unwrap());
+static RE_ARXIV: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)(?:\barXiv:\s*|https?://arxiv\.org/(?:abs|pdf)/)(?P<id>\d{4}\.\d{4,5}(?:v\d+)?|[a-z-]+(?:\.[a-z]{2})?/\d{7}(?:v\d+)?)",
+    )
+    .unwrap()
+});
+static RE_PMID: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\bPMID:?\s*(?P<id>\d{1,9})\b").unwrap());
+/// Groups of an identifier: runs of letters and digits between separators.
+static RE_GROUP: Lazy<Regex> = Lazy::new(|| Regex::new(r"[\p{L}\d]+").unwrap());
+
+/// `[identifiers]`: per-type policy for scholarly and product identifiers.
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(default)]
+pub(crate) struct IdentifierConfig {
+    /// Digital Object Identifiers (`doi:10.1145/3290605.3300233`, `https://doi.org/...`).
+    doi: IdentifierRule,
+    /// Book numbers (`ISBN 978-0-13-468599-1`).
+    isbn: IdentifierRule,
+    /// Serial numbers (`ISSN 1234-5678`).
+    issn: IdentifierRule,
+    /// arXiv preprints (`arXiv:2106.09685`, `arxiv.org/abs/...`).
+    arxiv: IdentifierRule,
+    /// PubMed IDs (`PMID: 12345678`).
+    pmid: IdentifierRule,
+}
+
+/// How one identifier type is read.
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(default)]
+struct IdentifierRule {
+    /// What happens to a recognized identifier.
+    policy: IdentifierPolicy,
+    /// Spoken name of the type, read before the groups in `digits` mode ("D O I" for DOIs).
+    name: Option<String>,
+    /// Text replacing the identifier in `label` mode ("D O I reference" for DOIs).
+    label: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+enum IdentifierPolicy {
+    /// Leave the identifier to the other stages.
+    Keep,
+    /// Remove the identifier.
+    Drop,
+    /// Replace the identifier with its `label`.
+    #[default]
+    Label,
+    /// Read the `name`, then each group character by character ("9 7 8, 0, 1 3, ...").
+    Digits,
+}
+
+/// Apply each identifier type's policy; returns the text and how many identifiers were found.
+pub(crate) fn handle_identifiers(text: &str, cfg: &IdentifierConfig) -> (String, usize) {
+    let mut count = 0;
+    let mut text = text.to_string();
+    for (re, rule, name, label) in [
+        (&*RE_DOI, &cfg.doi, "D O I", "D O I reference"),
+        (&*RE_ARXIV, &cfg.arxiv, "archive", "archive preprint"),
+        (&*RE_ISBN, &cfg.isbn, "I S B N", "I S B N reference"),
+        (&*RE_ISSN, &cfg.issn, "I S S N", "I S S N reference"),
+        (&*RE_PMID, &cfg.pmid, "PubMed", "PubMed reference"),
+    ] {
+        if rule.policy == IdentifierPolicy::Keep {
+            continue;
+        }
+        let name = rule.name.as_deref().unwrap_or(name);
+        let label = rule.label.as_deref().unwrap_or(label);
+        text = re
+            .replace_all(&text, |caps: &regex::Captures| {
+                let found = caps.get(0).map_or("", |found| found.as_str());
+                let id = caps
+                    .name("id")
+                    .or_else(|| caps.name("bare"))
+                    .map_or("", |id| id.as_str());
+                // Sentence punctuation after a DOI is not part of it.
+                let id_trimmed = id.trim_end_matches(['.', ',', ';', ':', ')', ']']);
+                let trailing = &found[found.len() - (id.len() - id_trimmed.len())..];
+                count += 1;
+                let spoken = match rule.policy {
+                    IdentifierPolicy::Drop => String::new(),
+                    IdentifierPolicy::Digits => format!("{name} {}", groups(id_trimmed)),
+                    _ => label.to_string(),
+                };
+                format!("{}{}", spoken.trim(), trailing)
+            })
+            .to_string();
+    }
+    (text, count)
+}
+
+/// Read an identifier group by group, each character on its own.
+fn groups(id: &str) -> String {
+    RE_GROUP
+        .find_iter(id)
+        .map(|group| {
+            group
+                .as_str()
+                .chars()
+                .map(String::from)
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
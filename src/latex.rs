@@ -0,0 +1,787 @@
+//! LaTeX input.
+//!
+//! Papers often only exist as `.tex` source. The preamble and `%` comments are dropped, the
+//! body is scanned command by command: sectioning becomes Markdown headings, emphasis and
+//! similar wrappers keep their text, citations, cross-references and labels disappear,
+//! `\footnote{...}` becomes a Markdown footnote for `[footnotes]`, lists become bullets and
+//! math is replaced by a placeholder or read out in words.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::math;
+
+/// Sectioning commands, from the top level down.
+const SECTIONS: &[&str] = &["chapter", "section", "subsection", "subsubsection"];
+/// Commands whose argument is kept as text.
+const WRAPPERS: &[&str] = &[
+    "emph",
+    "textit",
+    "textbf",
+    "textsl",
+    "textsc",
+    "texttt",
+    "textrm",
+    "textsf",
+    "textup",
+    "textmd",
+    "textnormal",
+    "underline",
+    "mbox",
+    "text",
+    "enquote",
+];
+/// Commands removed together with their arguments, and how many braced arguments they take.
+const DROPPED: &[(&str, usize)] = &[
+    ("cite", 1),
+    ("citep", 1),
+    ("citet", 1),
+    ("citealp", 1),
+    ("citealt", 1),
+    ("citeauthor", 1),
+    ("citeyear", 1),
+    ("parencite", 1),
+    ("textcite", 1),
+    ("autocite", 1),
+    ("footcite", 1),
+    ("nocite", 1),
+    ("ref", 1),
+    ("eqref", 1),
+    ("autoref", 1),
+    ("cref", 1),
+    ("Cref", 1),
+    ("pageref", 1),
+    ("nameref", 1),
+    ("vref", 1),
+    ("label", 1),
+    ("index", 1),
+    ("includegraphics", 1),
+    ("vspace", 1),
+    ("hspace", 1),
+    ("bibliography", 1),
+    ("bibliographystyle", 1),
+    ("input", 1),
+    ("include", 1),
+    ("title", 1),
+    ("author", 1),
+    ("date", 1),
+    ("thanks", 1),
+    ("pagestyle", 1),
+    ("thispagestyle", 1),
+    ("setlength", 2),
+    ("setcounter", 2),
+    ("addtocounter", 2),
+    ("textcolor", 1),
+    ("color", 1),
+];
+/// Environments holding math.
+const MATH_ENVIRONMENTS: &[&str] = &[
+    "equation",
+    "equation*",
+    "align",
+    "align*",
+    "alignat",
+    "alignat*",
+    "gather",
+    "gather*",
+    "multline",
+    "multline*",
+    "flalign",
+    "flalign*",
+    "eqnarray",
+    "eqnarray*",
+    "displaymath",
+    "math",
+];
+/// Environments whose `\begin` takes a braced argument that is not text.
+const ENVIRONMENT_ARGS: &[&str] = &["minipage", "multicols", "tabular", "tabularx", "wrapfigure"];
+
+static RE_TITLE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\\title\s*(?:\[[^\]]*\])?\s*\{").unwrap());
+
+/// `[latex]`: LaTeX source input (`io.input_format = "latex"`).
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub(crate) struct LatexConfig {
+    /// How math inside a paragraph (`$...$`, `\(...\)`) is read.
+    inline_math: MathMode,
+    /// How displayed math (`\[...\]`, `$$...$$`, `equation`, `align`, ...) is read.
+    display_math: MathMode,
+    /// Text standing in for math in `placeholder` mode.
+    math_placeholder: String,
+    /// Text standing in for a `\url{...}` (empty drops it); `\href` keeps its link text.
+    url_label: String,
+    /// Environments removed with their content.
+    skip_environments: Vec<String>,
+}
+
+impl Default for LatexConfig {
+    fn default() -> Self {
+        Self {
+            inline_math: MathMode::Speak,
+            display_math: MathMode::Placeholder,
+            math_placeholder: "a formula".to_string(),
+            url_label: "a link".to_string(),
+            skip_environments: [
+                "figure",
+                "figure*",
+                "table",
+                "table*",
+                "tabular",
+                "thebibliography",
+                "verbatim",
+                "lstlisting",
+                "minted",
+                "tikzpicture",
+                "comment",
+            ]
+            .map(str::to_string)
+            .to_vec(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+enum MathMode {
+    /// Replace the math with `math_placeholder`.
+    #[default]
+    Placeholder,
+    /// Read the math in words ("x squared plus y squared equals z squared").
+    Speak,
+    /// Keep the LaTeX source of the math.
+    Keep,
+}
+
+/// Convert a LaTeX document into plain text with Markdown headings and footnotes.
+pub(crate) fn to_text(tex: &str, cfg: &LatexConfig) -> String {
+    let tex = strip_comments(tex);
+    let title = RE_TITLE
+        .find(&tex)
+        .and_then(|found| braced(&tex[found.end() - 1..]))
+        .map(|(title, _)| title.to_string());
+    let body = match tex.find("\\begin{document}") {
+        Some(begin) => {
+            let start = begin + "\\begin{document}".len();
+            let end = tex[start..]
+                .find("\\end{document}")
+                .map_or(tex.len(), |end| start + end);
+            &tex[start..end]
+        }
+        None => tex.as_str(),
+    };
+    let top = if body.contains("\\chapter") { 0 } else { 1 };
+    let mut converter = Converter {
+        cfg,
+        title,
+        top,
+        footnotes: Vec::new(),
+    };
+    let mut text = converter.convert(body);
+    for (idx, note) in converter.footnotes.iter().enumerate() {
+        let note = note.split_whitespace().collect::<Vec<_>>().join(" ");
+        text.push_str(&format!("\n\n[^{}]: {note}", idx + 1));
+    }
+    tidy(&text)
+}
+
+struct Converter<'a> {
+    cfg: &'a LatexConfig,
+    title: Option<String>,
+    /// Index in `SECTIONS` of the command that becomes a level-one heading.
+    top: usize,
+    footnotes: Vec<String>,
+}
+
+impl Converter<'_> {
+    fn convert(&mut self, src: &str) -> String {
+        let mut out = String::with_capacity(src.len());
+        let mut rest = src;
+        while let Some(ch) = rest.chars().next() {
+            match ch {
+                '\\' => {
+                    let consumed = self.command(rest, &mut out);
+                    rest = &rest[consumed..];
+                }
+                '$' => {
+                    let display = rest.starts_with("$$");
+                    let delimiter = if display { "$$" } else { "$" };
+                    let inner = &rest[delimiter.len()..];
+                    let end = find_unescaped(inner, delimiter).unwrap_or(inner.len());
+                    self.push_math(&mut out, &inner[..end], display);
+                    rest = inner.get(end + delimiter.len()..).unwrap_or("");
+                }
+                '{' | '}' => rest = &rest[1..],
+                '~' => {
+                    out.push(' ');
+                    rest = &rest[1..];
+                }
+                _ => {
+                    out.push(ch);
+                    rest = &rest[ch.len_utf8()..];
+                }
+            }
+        }
+        out
+    }
+
+    /// Handle the command at the start of `text`; returns how many bytes it used.
+    fn command(&mut self, text: &str, out: &mut String) -> usize {
+        let after = &text[1..];
+        let name_len = after
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(after.len());
+        if name_len == 0 {
+            return 1 + self.symbol(after, out);
+        }
+        let name = &after[..name_len];
+        let mut used = 1 + name_len;
+        if text[used..].starts_with('*') {
+            used += 1;
+        }
+        let args = &text[used..];
+        match name {
+            "begin" => used + self.environment(args, out),
+            "end" => {
+                out.push_str("\n\n");
+                used + braced(args).map_or(0, |(_, len)| len)
+            }
+            "part" | "chapter" | "section" | "subsection" | "subsubsection" => {
+                let (title, len) = optional_then_braced(args);
+                let depth = SECTIONS.iter().position(|section| *section == name);
+                let level = depth.map_or(1, |depth| depth.saturating_sub(self.top) + 1);
+                let title = self.convert(title);
+                out.push_str(&format!("\n\n{} {}\n\n", "#".repeat(level), title.trim()));
+                used + len
+            }
+            "paragraph" | "subparagraph" => {
+                let (title, len) = optional_then_braced(args);
+                let title = self.convert(title);
+                let title = title.trim();
+                out.push_str("\n\n");
+                out.push_str(title);
+                if !title.ends_with(['.', '?', '!', ':']) {
+                    out.push('.');
+                }
+                out.push(' ');
+                used + len
+            }
+            "maketitle" => {
+                if let Some(title) = self.title.clone() {
+                    let title = self.convert(&title);
+                    out.push_str(&format!("\n\n# {}\n\n", title.trim()));
+                }
+                used
+            }
+            "footnote" => {
+                let (note, len) = optional_then_braced(args);
+                let note = self.convert(note);
+                self.footnotes.push(note);
+                out.push_str(&format!("[^{}]", self.footnotes.len()));
+                used + len
+            }
+            "href" => {
+                let Some((_, url_len)) = braced(args) else {
+                    return used;
+                };
+                let Some((label, label_len)) = braced(&args[url_len..]) else {
+                    return used + url_len;
+                };
+                out.push_str(&self.convert(label));
+                used + url_len + label_len
+            }
+            "url" | "nolinkurl" => {
+                let Some((_, len)) = braced(args) else {
+                    return used;
+                };
+                out.push_str(&self.cfg.url_label);
+                used + len
+            }
+            "verb" => {
+                // \verb|text| with any delimiter.
+                let Some(delimiter) = args.chars().next() else {
+                    return used;
+                };
+                let inner = &args[delimiter.len_utf8()..];
+                let end = inner.find(delimiter).unwrap_or(inner.len());
+                out.push_str(&inner[..end]);
+                used + delimiter.len_utf8() + (end + delimiter.len_utf8()).min(inner.len())
+            }
+            "item" => {
+                let trimmed = out.trim_end().len();
+                out.truncate(trimmed);
+                out.push_str("\n- ");
+                match args.trim_start().strip_prefix('[') {
+                    Some(_) => {
+                        let (label, len) = optional(args);
+                        let label = self.convert(label);
+                        out.push_str(label.trim());
+                        out.push_str(": ");
+                        used + len
+                    }
+                    None => used,
+                }
+            }
+            "par" => {
+                out.push_str("\n\n");
+                used
+            }
+            "newline" | "linebreak" => {
+                out.push('\n');
+                used
+            }
+            "ldots" | "dots" | "textellipsis" => {
+                out.push('…');
+                used
+            }
+            "textendash" => {
+                out.push('–');
+                used
+            }
+            "textemdash" => {
+                out.push('—');
+                used
+            }
+            "S" => {
+                out.push('§');
+                used
+            }
+            "textdegree" | "degree" => {
+                out.push('°');
+                used
+            }
+            "c" | "v" | "u" | "H" => {
+                let (base, len) = accent_base(args);
+                out.push_str(base);
+                out.push(match name {
+                    "c" => '\u{327}',
+                    "v" => '\u{30c}',
+                    "u" => '\u{306}',
+                    _ => '\u{30b}',
+                });
+                used + len
+            }
+            "LaTeX" | "TeX" => {
+                out.push_str(name);
+                used
+            }
+            name if WRAPPERS.contains(&name) => {
+                let (inner, len) = optional_then_braced(args);
+                out.push_str(&self.convert(inner));
+                used + len
+            }
+            name => match DROPPED.iter().find(|(dropped, _)| *dropped == name) {
+                Some(&(_, count)) => used + skip_arguments(args, count),
+                // Declarations (\centering, \small, \em) and unknown commands are dropped; a
+                // braced argument stays as text.
+                None => used + optional(args).1,
+            },
+        }
+    }
+
+    /// A control symbol: escapes, accents, spacing and the `\(` / `\[` math delimiters.
+    fn symbol(&mut self, after: &str, out: &mut String) -> usize {
+        let Some(ch) = after.chars().next() else {
+            return 0;
+        };
+        let rest = &after[ch.len_utf8()..];
+        match ch {
+            '\\' => {
+                out.push('\n');
+                1 + optional(rest).1
+            }
+            '(' | '[' => {
+                let closing = if ch == '(' { "\\)" } else { "\\]" };
+                let end = rest.find(closing).unwrap_or(rest.len());
+                self.push_math(out, &rest[..end], ch == '[');
+                1 + (end + closing.len()).min(rest.len())
+            }
+            '\'' | '`' | '^' | '"' | '~' | '=' | '.' => {
+                let (base, len) = accent_base(rest);
+                out.push_str(base);
+                out.push(match ch {
+                    '\'' => '\u{301}',
+                    '`' => '\u{300}',
+                    '^' => '\u{302}',
+                    '"' => '\u{308}',
+                    '~' => '\u{303}',
+                    '=' => '\u{304}',
+                    _ => '\u{307}',
+                });
+                1 + len
+            }
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                out.push(ch);
+                1
+            }
+            ',' | ';' | ':' | ' ' => {
+                out.push(' ');
+                1
+            }
+            _ => 1,
+        }
+    }
+
+    /// Handle `\begin{name}...`; returns how many bytes after `\begin` it used.
+    fn environment(&mut self, args: &str, out: &mut String) -> usize {
+        let Some((name, name_len)) = braced(args) else {
+            return 0;
+        };
+        let content = &args[name_len..];
+        let is_math = MATH_ENVIRONMENTS.contains(&name);
+        if is_math || self.cfg.skip_environments.iter().any(|env| env == name) {
+            let end = find_end(content, name);
+            if is_math {
+                self.push_math(out, &content[..end], true);
+            }
+            let end_tag = format!("\\end{{{name}}}");
+            return name_len + (end + end_tag.len()).min(content.len());
+        }
+        out.push_str("\n\n");
+        if name == "abstract" {
+            out.push_str("## Abstract\n\n");
+        }
+        let mut used = name_len + optional(content).1;
+        if ENVIRONMENT_ARGS.contains(&name) {
+            used += braced(&args[used..]).map_or(0, |(_, len)| len);
+        }
+        used
+    }
+
+    fn push_math(&self, out: &mut String, math: &str, display: bool) {
+        let mode = if display {
+            self.cfg.display_math
+        } else {
+            self.cfg.inline_math
+        };
+        let rendered = match mode {
+            MathMode::Placeholder => self.cfg.math_placeholder.clone(),
+            MathMode::Speak => speak_math(math),
+            MathMode::Keep => math.trim().to_string(),
+        };
+        out.push(' ');
+        out.push_str(&rendered);
+        out.push(' ');
+    }
+}
+
+/// The letter an accent applies to: `{e}` or a single character.
+fn accent_base(text: &str) -> (&str, usize) {
+    if let Some(found) = braced(text) {
+        return found;
+    }
+    let trimmed = text.trim_start();
+    let len = trimmed.chars().next().map_or(0, char::len_utf8);
+    (&trimmed[..len], text.len() - trimmed.len() + len)
+}
+
+/// Remove `%` comments; a line holding only a comment disappears with its newline.
+fn strip_comments(tex: &str) -> String {
+    tex.lines()
+        .filter_map(|line| match find_unescaped(line, "%") {
+            Some(0) => None,
+            Some(pos) if line[..pos].trim().is_empty() => None,
+            Some(pos) => Some(&line[..pos]),
+            None => Some(line),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Byte offset of the first `needle` in `text` not escaped with a backslash.
+fn find_unescaped(text: &str, needle: &str) -> Option<usize> {
+    let mut from = 0;
+    while let Some(pos) = text[from..].find(needle) {
+        let at = from + pos;
+        let backslashes = text[..at].chars().rev().take_while(|&c| c == '\\').count();
+        if backslashes % 2 == 0 {
+            return Some(at);
+        }
+        from = at + needle.len();
+    }
+    None
+}
+
+/// Offset of the `\end{name}` closing an environment, counting nested ones of the same name.
+fn find_end(text: &str, name: &str) -> usize {
+    let begin = format!("\\begin{{{name}}}");
+    let end = format!("\\end{{{name}}}");
+    let mut depth = 0;
+    let mut from = 0;
+    while let Some(pos) = text[from..].find(&end) {
+        let at = from + pos;
+        depth += text[from..at].matches(&begin).count();
+        if depth == 0 {
+            return at;
+        }
+        depth -= 1;
+        from = at + end.len();
+    }
+    text.len()
+}
+
+/// The content of a `{...}` group at the start of `text` (after spaces) and the bytes used.
+fn braced(text: &str) -> Option<(&str, usize)> {
+    delimited(text, '{', '}')
+}
+
+/// The content of an optional `[...]` argument, or an empty string.
+fn optional(text: &str) -> (&str, usize) {
+    delimited(text, '[', ']').unwrap_or(("", 0))
+}
+
+fn optional_then_braced(text: &str) -> (&str, usize) {
+    let (_, skipped) = optional(text);
+    braced(&text[skipped..]).map_or(("", skipped), |(inner, len)| (inner, skipped + len))
+}
+
+fn delimited(text: &str, open: char, close: char) -> Option<(&str, usize)> {
+    let start = text.len() - text.trim_start_matches([' ', '\t']).len();
+    if !text[start..].starts_with(open) {
+        return None;
+    }
+    let mut depth = 0usize;
+    let mut escaped = false;
+    for (idx, ch) in text[start..].char_indices() {
+        match ch {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            ch if ch == open => depth += 1,
+            ch if ch == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((&text[start + 1..start + idx], start + idx + 1));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Skip optional arguments and `count` braced arguments.
+fn skip_arguments(text: &str, count: usize) -> usize {
+    let mut used = 0;
+    for _ in 0..count {
+        while let Some((_, len)) = delimited(&text[used..], '[', ']') {
+            used += len;
+        }
+        match braced(&text[used..]) {
+            Some((_, len)) => used += len,
+            None => break,
+        }
+    }
+    used
+}
+
+/// TeX quotes and dashes, then one space between words and at most one blank line.
+fn tidy(text: &str) -> String {
+    let text = text
+        .replace("``", "“")
+        .replace("''", "”")
+        .replace('`', "‘")
+        .replace("---", "—")
+        .replace("--", "–");
+    let mut out = String::with_capacity(text.len());
+    let mut blank = 0;
+    for line in text.lines() {
+        let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if line.is_empty() {
+            blank += 1;
+            continue;
+        }
+        if !out.is_empty() {
+            out.push_str(if blank > 0 { "\n\n" } else { "\n" });
+        }
+        out.push_str(&line);
+        blank = 0;
+    }
+    out
+}
+
+/// Read LaTeX math in words: "x^2 + y^2 = z^2" becomes "x squared plus y squared equals z
+/// squared".
+fn speak_math(math: &str) -> String {
+    let mut words = String::new();
+    let mut rest = math;
+    let mut previous = ' ';
+    while let Some(ch) = rest.chars().next() {
+        rest = &rest[ch.len_utf8()..];
+        match ch {
+            '\\' => {
+                // A control word, or a control symbol such as `\,` or `\\`.
+                let name_len = match rest.find(|c: char| !c.is_ascii_alphabetic()) {
+                    Some(0) => rest.chars().next().map_or(0, char::len_utf8),
+                    Some(len) => len,
+                    None => rest.len(),
+                };
+                let name = &rest[..name_len];
+                rest = &rest[name_len..];
+                let (spoken, used) = speak_command(name, rest);
+                rest = &rest[used..];
+                words.push_str(&format!(" {spoken} "));
+            }
+            '^' | '_' => {
+                let (arg, used) = math_argument(rest);
+                rest = &rest[used..];
+                let spoken = speak_math(arg);
+                words.push_str(&match (ch, spoken.as_str()) {
+                    ('^', "prime") => " prime ".to_string(),
+                    ('^', _) => format!(" {} ", math::power_words(&spoken)),
+                    _ => format!(" sub {spoken} "),
+                });
+            }
+            '+' | '-' | '=' | '<' | '>' | '/' | '*' | '!' => {
+                words.push_str(&format!(" {} ", math::symbol_word(ch).unwrap_or_default()));
+            }
+            '\'' => words.push_str(" prime "),
+            ',' => words.push_str(", "),
+            '&' | '{' | '}' | '(' | ')' | '[' | ']' | '|' | '~' => words.push(' '),
+            // Letters are variables: "xy" is "x y", "2x" is "2 x".
+            ch if ch.is_alphabetic() => {
+                if previous.is_alphanumeric() {
+                    words.push(' ');
+                }
+                words.push(ch);
+            }
+            ch if ch.is_ascii_digit() && previous.is_alphabetic() => {
+                words.push(' ');
+                words.push(ch);
+            }
+            ch => words.push(ch),
+        }
+        previous = ch;
+    }
+    words
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace(" ,", ",")
+}
+
+/// The words for a math command and how many bytes of arguments it used.
+fn speak_command(name: &str, args: &str) -> (String, usize) {
+    let word = match name {
+        "frac" | "dfrac" | "tfrac" => {
+            let (numerator, first) = math_argument(args);
+            let (denominator, second) = math_argument(&args[first..]);
+            return (
+                format!("{} over {}", speak_math(numerator), speak_math(denominator)),
+                first + second,
+            );
+        }
+        "sqrt" => {
+            let (root, skipped) = optional(args);
+            let (radicand, len) = math_argument(&args[skipped..]);
+            let spoken = math::root_words(&speak_math(root), &speak_math(radicand));
+            return (spoken, skipped + len);
+        }
+        "text" | "mathrm" | "mathbf" | "mathit" | "mathsf" | "mathtt" | "mathcal" | "mathbb"
+        | "boldsymbol" | "operatorname" | "mbox" => {
+            let (inner, len) = math_argument(args);
+            return (speak_math(inner), len);
+        }
+        "label" | "tag" => return (String::new(), skip_arguments(args, 1)),
+        "sum" | "prod" | "int" | "lim" => {
+            let (lower, upper, len) = limits(args);
+            let noun = match name {
+                "sum" => "the sum",
+                "prod" => "the product",
+                "int" => "the integral",
+                _ => "the limit",
+            };
+            let spoken = match (lower, upper) {
+                (Some(lower), _) if name == "lim" => format!("{noun} as {} of", speak_math(lower)),
+                (Some(lower), Some(upper)) => format!(
+                    "{noun} from {} to {} of",
+                    speak_math(lower),
+                    speak_math(upper)
+                ),
+                (Some(lower), None) => format!("{noun} over {} of", speak_math(lower)),
+                _ => format!("{noun} of"),
+            };
+            return (spoken, len);
+        }
+        "times" | "cdot" | "ast" => "times",
+        "ldots" | "cdots" | "dots" => "and so on",
+        "prime" => "prime",
+        "circ" => "degrees",
+        "partial" => "partial",
+        "nabla" => "nabla",
+        "in" => "in",
+        "to" | "rightarrow" => "to",
+        "Rightarrow" | "implies" => "implies",
+        "iff" | "Leftrightarrow" => "if and only if",
+        "varepsilon" => "epsilon",
+        "vartheta" => "theta",
+        "varphi" => "phi",
+        "\\" => ",",
+        name if math::is_greek_name(name) => name,
+        // \left, \right, \big, spacing and unknown commands are silent.
+        name => math::function_word(name)
+            .or_else(|| relation_symbol(name).and_then(math::symbol_word))
+            .unwrap_or_default(),
+    };
+    (word.to_string(), 0)
+}
+
+/// The Unicode symbol for a LaTeX operator or relation, read with the `[math]` word table.
+fn relation_symbol(name: &str) -> Option<char> {
+    Some(match name {
+        "div" => '÷',
+        "pm" => '±',
+        "mp" => '∓',
+        "leq" | "le" => '≤',
+        "geq" | "ge" => '≥',
+        "neq" | "ne" => '≠',
+        "approx" => '≈',
+        "equiv" => '≡',
+        "sim" => '∼',
+        "propto" => '∝',
+        "ll" => '≪',
+        "gg" => '≫',
+        "infty" => '∞',
+        "notin" => '∉',
+        "subset" | "subseteq" => '⊂',
+        "cup" => '∪',
+        "cap" => '∩',
+        "forall" => '∀',
+        "exists" => '∃',
+        _ => return None,
+    })
+}
+
+/// A `^` or `_` argument: a braced group, a command, or one character.
+fn math_argument(text: &str) -> (&str, usize) {
+    let trimmed = text.trim_start();
+    let skipped = text.len() - trimmed.len();
+    if let Some((inner, len)) = braced(trimmed) {
+        return (inner, skipped + len);
+    }
+    if let Some(command) = trimmed.strip_prefix('\\') {
+        let len = command
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(command.len());
+        return (&trimmed[..1 + len], skipped + 1 + len);
+    }
+    let len = trimmed.chars().next().map_or(0, char::len_utf8);
+    (&trimmed[..len], skipped + len)
+}
+
+/// The `_{...}` and `^{...}` limits after a big operator, in either order.
+fn limits(text: &str) -> (Option<&str>, Option<&str>, usize) {
+    let (mut lower, mut upper, mut used) = (None, None, 0);
+    loop {
+        let rest = text[used..].trim_start();
+        let skipped = text.len() - used - rest.len();
+        let slot = match rest.chars().next() {
+            Some('_') if lower.is_none() => &mut lower,
+            Some('^') if upper.is_none() => &mut upper,
+            _ => return (lower, upper, used),
+        };
+        let (arg, len) = math_argument(&rest[1..]);
+        *slot = Some(arg);
+        used += skipped + 1 + len;
+    }
+}
@@ -0,0 +1,485 @@
+//! External pronunciation lexicons.
+//!
+//! Profiles can point `[lexicons] files` at TOML, CSV or TSV dictionaries so large domain
+//! word lists can be shared between profiles. Every entry (inline or from a file) is resolved
+//! into a [`LexiconEntry`] with explicit match options, and the merged set is what the
+//! brand, replacement and acronym stages consume.
+
+use anyhow::{Context, Result, bail};
+use log::{debug, info, warn};
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{AbbreviationConfig, PronunciationConfig, ssml};
+
+/// `[lexicons]`: external pronunciation dictionaries merged into the profile.
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(default)]
+pub(crate) struct LexiconConfig {
+    /// Lexicon files (`.toml`, `.csv` or `.tsv`), resolved relative to the profile.
+    files: Vec<PathBuf>,
+    /// Abort loading when two entries for the same term disagree.
+    strict: bool,
+}
+
+/// Which stage a lexicon entry feeds.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize, JsonSchema,
+)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum EntryKind {
+    /// Whole-word spelling applied by the brand stage (`pronunciation.brand_map`).
+    Brand,
+    /// Literal replacement (`pronunciation.replacements`).
+    Replacement,
+    /// Acronym spelled through `letter_sounds`, or read as `say` when given.
+    Acronym,
+    /// Spoken form of a single letter or digit (`abbreviations.letter_sounds`).
+    Letter,
+}
+
+/// One entry as written in a lexicon file; unset options fall back to per-kind defaults.
+#[derive(Debug, Deserialize)]
+struct RawEntry {
+    kind: EntryKind,
+    term: String,
+    #[serde(default)]
+    say: String,
+    #[serde(default)]
+    case: Option<CaseMatch>,
+    /// Shorthand for `case = "exact"` (`true`) or `case = "insensitive"` (`false`).
+    #[serde(default)]
+    case_sensitive: Option<bool>,
+    #[serde(default)]
+    whole_word: Option<bool>,
+    #[serde(default)]
+    mode: Option<AcronymMode>,
+    #[serde(default)]
+    pos: Option<String>,
+    #[serde(default)]
+    priority: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TomlLexicon {
+    #[serde(default, rename = "entry")]
+    entries: Vec<RawEntry>,
+}
+
+/// How an acronym is read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum AcronymMode {
+    /// Letter by letter through `letter_sounds` ("see ess ess").
+    Spell,
+    /// As a word; written in title case so engines do not spell it ("Nasa").
+    Word,
+    /// Replaced by the entry's `say` respelling ("sequel").
+    Custom,
+}
+
+/// Which capitalizations of a term are rewritten.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum CaseMatch {
+    /// Only the exact spelling of the term.
+    Exact,
+    /// Any capitalization.
+    Insensitive,
+    /// Only when every letter of the match is uppercase ("US" but not "us").
+    AllCaps,
+    /// Only when the match starts with an uppercase letter ("Apple" but not "apple").
+    InitialCap,
+}
+
+impl CaseMatch {
+    /// Whether `matched` (already matched case-insensitively) has an accepted capitalization.
+    pub(crate) fn accepts(self, matched: &str) -> bool {
+        match self {
+            CaseMatch::Exact | CaseMatch::Insensitive => true,
+            CaseMatch::AllCaps => !matched.chars().any(char::is_lowercase),
+            CaseMatch::InitialCap => matched
+                .chars()
+                .find(|c| c.is_alphabetic())
+                .is_some_and(char::is_uppercase),
+        }
+    }
+}
+
+/// Per-kind match defaults for entries that do not set their own options.
+pub(crate) struct MatchDefaults {
+    pub(crate) brand_case: CaseMatch,
+    pub(crate) acronym_case: CaseMatch,
+}
+
+impl MatchDefaults {
+    /// Default `(case, whole_word)` for a kind.
+    fn for_kind(&self, kind: EntryKind) -> (CaseMatch, bool) {
+        match kind {
+            EntryKind::Brand => (self.brand_case, true),
+            EntryKind::Acronym => (self.acronym_case, true),
+            EntryKind::Replacement => (CaseMatch::Exact, false),
+            EntryKind::Letter => (CaseMatch::Insensitive, false),
+        }
+    }
+}
+
+/// A resolved lexicon entry.
+#[derive(Debug, Clone)]
+pub(crate) struct LexiconEntry {
+    pub(crate) kind: EntryKind,
+    pub(crate) term: String,
+    pub(crate) say: String,
+    pub(crate) case: CaseMatch,
+    pub(crate) whole_word: bool,
+    /// How an acronym entry is read; ignored for other kinds.
+    pub(crate) mode: AcronymMode,
+    pub(crate) pos: Option<String>,
+    pub(crate) priority: i32,
+    /// Where the entry came from, for conflict reports.
+    pub(crate) source: String,
+}
+
+impl LexiconEntry {
+    fn new(kind: EntryKind, term: &str, say: &str, source: &str, defaults: &MatchDefaults) -> Self {
+        let (case, whole_word) = defaults.for_kind(kind);
+        Self {
+            kind,
+            term: term.to_string(),
+            say: say.to_string(),
+            case,
+            whole_word,
+            mode: if say.is_empty() {
+                AcronymMode::Spell
+            } else {
+                AcronymMode::Custom
+            },
+            pos: None,
+            priority: 0,
+            source: source.to_string(),
+        }
+    }
+
+    fn from_raw(raw: RawEntry, source: &str, defaults: &MatchDefaults) -> Self {
+        let mut entry = Self::new(raw.kind, raw.term.trim(), &raw.say, source, defaults);
+        if let Some(case_sensitive) = raw.case_sensitive {
+            entry.case = if case_sensitive {
+                CaseMatch::Exact
+            } else {
+                CaseMatch::Insensitive
+            };
+        }
+        if let Some(case) = raw.case {
+            entry.case = case;
+        }
+        if let Some(whole_word) = raw.whole_word {
+            entry.whole_word = whole_word;
+        }
+        entry.pos = raw.pos.filter(|pos| !pos.trim().is_empty());
+        if let Some(mode) = raw.mode {
+            entry.mode = mode;
+        }
+        entry.priority = raw.priority.unwrap_or(0);
+        entry
+    }
+
+    /// Key used for duplicate detection: entries with the same key compete for the same text.
+    fn key(&self) -> (EntryKind, String, Option<String>) {
+        let term = if self.case == CaseMatch::Exact {
+            self.term.clone()
+        } else {
+            self.term.to_lowercase()
+        };
+        (self.kind, term, self.pos.clone())
+    }
+
+    /// Regex matching this entry according to its word-boundary option.
+    ///
+    /// Everything but `exact` matches case-insensitively; callers filter the capitalization
+    /// of the `term` group with [`LexiconEntry::accepts`]. Whole-word entries also match a
+    /// lowercase plural or possessive suffix ("APIs", "MySQL's") in the `suffix` group and the
+    /// hyphen of a compound ("HTML-based") in the `hyphen` group. `infix` (such as trailing
+    /// digits) is matched between the term and the suffix.
+    pub(crate) fn pattern_with_infix(&self, infix: &str) -> Regex {
+        let mut pattern = format!("(?P<term>{}){}", regex::escape(&self.term), infix);
+        if self.whole_word {
+            pattern = format!(r"\b{pattern}(?-i:(?P<suffix>'s|s))?\b(?:(?P<hyphen>-)\b)?");
+        }
+        if self.case != CaseMatch::Exact {
+            pattern = format!("(?i){pattern}");
+        }
+        Regex::new(&pattern).unwrap()
+    }
+
+    /// Whether a match of the `term` group has a capitalization this entry rewrites.
+    pub(crate) fn accepts(&self, term: &str) -> bool {
+        self.case.accepts(term)
+    }
+
+    /// Replace every accepted match of the entry with its `say` text, keeping suffixes attached.
+    ///
+    /// With `ssml`, the match is kept as a substitution whose alias is the spoken form.
+    pub(crate) fn replace_all(&self, text: &str, ssml: bool) -> String {
+        self.pattern_with_infix("")
+            .replace_all(text, |caps: &regex::Captures| {
+                if !self.accepts(&caps["term"]) {
+                    return caps[0].to_string();
+                }
+                let suffix = caps.name("suffix").map_or("", |suffix| suffix.as_str());
+                let mut spoken = attach_suffix(self.say.clone(), suffix);
+                if ssml {
+                    spoken = ssml::substitute(caps[0].trim_end_matches('-'), &spoken);
+                }
+                if caps.name("hyphen").is_some() {
+                    spoken.push(' ');
+                }
+                spoken
+            })
+            .to_string()
+    }
+}
+
+/// Attach a plural (`s`) or possessive (`'s`) suffix to a spoken form.
+///
+/// Plurals inflect the last spoken word ("ay pee eye" becomes "ay pee eyes", "ess" becomes
+/// "esses"); a trailing period from dotted spellings ("My S. Q. L.") is dropped first so the
+/// suffix attaches cleanly.
+pub(crate) fn attach_suffix(mut spoken: String, suffix: &str) -> String {
+    if suffix.is_empty() || spoken.is_empty() {
+        return spoken;
+    }
+    while spoken.ends_with(['.', ' ']) {
+        spoken.pop();
+    }
+    if suffix == "s" {
+        let lower = spoken.to_lowercase();
+        if ["s", "x", "z", "ch", "sh"]
+            .iter()
+            .any(|ending| lower.ends_with(ending))
+        {
+            spoken.push_str("es");
+        } else {
+            spoken.push('s');
+        }
+    } else {
+        spoken.push_str(suffix);
+    }
+    spoken
+}
+
+/// The merged lexicon the pipeline stages read from.
+#[derive(Debug, Default)]
+pub(crate) struct Lexicon {
+    pub(crate) brands: Vec<LexiconEntry>,
+    pub(crate) replacements: Vec<LexiconEntry>,
+    pub(crate) acronyms: Vec<LexiconEntry>,
+    pub(crate) letter_sounds: BTreeMap<String, String>,
+}
+
+impl Lexicon {
+    /// Merge the inline profile tables with the configured lexicon files.
+    ///
+    /// For entries competing for the same term, the higher `priority` wins; ties go to the
+    /// later source, and the profile itself counts as the last source so inline entries
+    /// override shared dictionaries.
+    pub(crate) fn load(
+        cfg: &LexiconConfig,
+        base_dir: &Path,
+        pronunciation: &PronunciationConfig,
+        abbreviations: &AbbreviationConfig,
+    ) -> Result<Self> {
+        let defaults = MatchDefaults {
+            brand_case: pronunciation.case,
+            acronym_case: abbreviations.case,
+        };
+        let mut entries = Vec::new();
+        for file in &cfg.files {
+            let path = if file.is_absolute() {
+                file.clone()
+            } else {
+                base_dir.join(file)
+            };
+            let loaded = read_lexicon_file(&path, &defaults)?;
+            info!(
+                "Loaded {} lexicon entries from {}",
+                loaded.len(),
+                path.display()
+            );
+            entries.extend(loaded);
+        }
+        entries.extend(profile_entries(pronunciation, abbreviations, &defaults));
+
+        let mut winners: BTreeMap<(EntryKind, String, Option<String>), LexiconEntry> =
+            BTreeMap::new();
+        let mut duplicates = 0;
+        let mut conflicts = 0;
+        for entry in entries {
+            let key = entry.key();
+            let Some(existing) = winners.get(&key) else {
+                winners.insert(key, entry);
+                continue;
+            };
+            if existing.say == entry.say && existing.mode == entry.mode {
+                duplicates += 1;
+                debug!(
+                    "duplicate {:?} entry {:?} in {} and {}",
+                    entry.kind, entry.term, existing.source, entry.source
+                );
+            } else {
+                conflicts += 1;
+                let message = format!(
+                    "conflicting {:?} entry {:?}: {:?} ({}, priority {}) vs {:?} ({}, priority {})",
+                    entry.kind,
+                    entry.term,
+                    existing.say,
+                    existing.source,
+                    existing.priority,
+                    entry.say,
+                    entry.source,
+                    entry.priority
+                );
+                if cfg.strict {
+                    bail!(message);
+                }
+                warn!("{message}");
+            }
+            if entry.priority >= existing.priority {
+                winners.insert(key, entry);
+            }
+        }
+        if duplicates > 0 || conflicts > 0 {
+            info!("Lexicon merge: {duplicates} duplicate entries, {conflicts} conflicts");
+        }
+
+        // There is no part-of-speech tagger, so only the strongest variant of a homograph applies.
+        let mut variants: Vec<LexiconEntry> = winners.into_values().collect();
+        variants.sort_by_key(|entry| Reverse(entry.priority));
+        let mut seen = BTreeSet::new();
+        let mut lexicon = Lexicon::default();
+        for entry in variants {
+            let (kind, term, _) = entry.key();
+            if !seen.insert((kind, term)) {
+                debug!(
+                    "skipping {:?} variant of {:?} from {} (pos {:?})",
+                    entry.kind, entry.term, entry.source, entry.pos
+                );
+                continue;
+            }
+            match entry.kind {
+                EntryKind::Brand => lexicon.brands.push(entry),
+                EntryKind::Replacement => lexicon.replacements.push(entry),
+                EntryKind::Acronym => lexicon.acronyms.push(entry),
+                EntryKind::Letter => {
+                    lexicon
+                        .letter_sounds
+                        .insert(entry.term.to_uppercase(), entry.say);
+                }
+            }
+        }
+        Ok(lexicon)
+    }
+}
+
+fn profile_entries(
+    pronunciation: &PronunciationConfig,
+    abbreviations: &AbbreviationConfig,
+    defaults: &MatchDefaults,
+) -> Vec<LexiconEntry> {
+    let source = "profile";
+    let mut entries = Vec::new();
+    for (term, say) in &abbreviations.letter_sounds {
+        entries.push(LexiconEntry::new(
+            EntryKind::Letter,
+            term,
+            say,
+            source,
+            defaults,
+        ));
+    }
+    // A respelling in `map` beats `pronounceable`, which beats plain spelling from `tokens`.
+    let mut acronyms: BTreeMap<String, (AcronymMode, &str)> = BTreeMap::new();
+    for token in &abbreviations.tokens {
+        acronyms.insert(token.to_uppercase(), (AcronymMode::Spell, ""));
+    }
+    for token in &abbreviations.pronounceable {
+        acronyms.insert(token.to_uppercase(), (AcronymMode::Word, ""));
+    }
+    for (token, say) in &abbreviations.map {
+        acronyms.insert(token.to_uppercase(), (AcronymMode::Custom, say.as_str()));
+    }
+    for (token, (mode, say)) in acronyms {
+        let mut entry = LexiconEntry::new(EntryKind::Acronym, &token, say, source, defaults);
+        entry.mode = mode;
+        entries.push(entry);
+    }
+    for (term, say) in &pronunciation.replacements {
+        entries.push(LexiconEntry::new(
+            EntryKind::Replacement,
+            term,
+            say,
+            source,
+            defaults,
+        ));
+    }
+    for (term, say) in &pronunciation.brand_map {
+        entries.push(LexiconEntry::new(
+            EntryKind::Brand,
+            term,
+            say,
+            source,
+            defaults,
+        ));
+    }
+    entries
+}
+
+fn read_lexicon_file(path: &Path, defaults: &MatchDefaults) -> Result<Vec<LexiconEntry>> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read lexicon {}", path.display()))?;
+    let source = path.display().to_string();
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let raw: Vec<RawEntry> = match extension.as_str() {
+        "toml" => {
+            toml::from_str::<TomlLexicon>(&contents)
+                .with_context(|| format!("failed to parse {}", path.display()))?
+                .entries
+        }
+        "csv" => read_delimited(&contents, b',', path)?,
+        "tsv" => read_delimited(&contents, b'\t', path)?,
+        other => bail!(
+            "unsupported lexicon format {:?} for {} (expected toml, csv or tsv)",
+            other,
+            path.display()
+        ),
+    };
+    Ok(raw
+        .into_iter()
+        .filter(|entry| !entry.term.trim().is_empty())
+        .map(|entry| LexiconEntry::from_raw(entry, &source, defaults))
+        .collect())
+}
+
+fn read_delimited(contents: &str, delimiter: u8, path: &Path) -> Result<Vec<RawEntry>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .comment(Some(b'#'))
+        .flexible(true)
+        .trim(csv::Trim::Headers)
+        .from_reader(contents.as_bytes());
+    reader
+        .deserialize()
+        .enumerate()
+        .map(|(row, record)| {
+            record.with_context(|| format!("{}: bad entry on data row {}", path.display(), row + 1))
+        })
+        .collect()
+}
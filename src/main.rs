@@ -1,16 +1,54 @@
 use anyhow::{Context, Result};
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use env_logger::Builder;
 use log::{info, warn};
 use once_cell::sync::Lazy;
 use regex::Regex;
-use serde::Deserialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use std::cmp::Reverse;
 use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use unicode_normalization::UnicodeNormalization;
 
+use acronyms::{DetectedAcronym, expand_acronyms, expand_unknown_acronyms};
+use backmatter::BackMatterConfig;
+use chapters::{Chapter, ChapterConfig};
+use charset::{CharsetConfig, UnsupportedLetter};
+use epub::EpubConfig;
+use footnotes::FootnoteConfig;
+use html::HtmlConfig;
+use identifiers::IdentifierConfig;
+use latex::LatexConfig;
+use lexicon::{CaseMatch, Lexicon, LexiconConfig, LexiconEntry};
+use math::MathConfig;
+use pages::{PageConfig, PageStats};
+use pauses::{PauseConfig, PauseEvent};
+use subtitles::SubtitleConfig;
+use symbols::SymbolConfig;
+use transcript::TranscriptConfig;
+
+mod acronyms;
+mod backmatter;
+mod chapters;
+mod charset;
+mod citations;
+mod epub;
+mod footnotes;
+mod html;
+mod identifiers;
+mod latex;
+mod lexicon;
+mod math;
+mod pages;
+mod pauses;
+mod ssml;
+mod subtitles;
+mod symbols;
+mod transcript;
+mod wrap;
+
 static RE_CODE_FENCE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)```.*?```").unwrap());
 static RE_INLINE_CODE: Lazy<Regex> = Lazy::new(|| Regex::new(r"`([^`]+)`").unwrap());
 static RE_STACKED_NUM_CITE: Lazy<Regex> =
@@ -22,7 +60,8 @@ static RE_MULTI_SPACE: Lazy<Regex> = Lazy::new(|| Regex::new(r"[ \t\u{00A0}]+").
 static RE_GENERIC_BRACKETS: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"\[[^\]]*?\d[^\]]*?\]").unwrap());
 static RE_GENERIC_PARENS: Lazy<Regex> = Lazy::new(|| Regex::new(r"\([^)]*?\d[^)]*?\)").unwrap());
-static RE_SPACE_BEFORE_PUNCT: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s+([,.;:!?])").unwrap());
+static RE_SPACE_BEFORE_PUNCT: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[ \t\u{00A0}]+([,.;:!?])").unwrap());
 static RE_PUNCT_RUN: Lazy<Regex> = Lazy::new(|| Regex::new(r"([=\\-~]{5,})").unwrap());
 static RE_HTML_OPEN: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"<\s*([a-zA-Z][a-zA-Z0-9]*)[^>]*>").unwrap());
@@ -34,23 +73,38 @@ static RE_COMMA_BEFORE_PERIOD: Lazy<Regex> = Lazy::new(|| Regex::new(r",\s*\.").
 #[command(
     author,
     version,
-    about = "Clean and normalize text before feeding it into XTTS-style TTS engines."
+    about = "Clean and normalize text before feeding it into XTTS-style TTS engines.",
+    subcommand_negates_reqs = true
 )]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// File that should be cleaned.
-    #[arg(short, long, value_name = "FILE")]
-    input: PathBuf,
+    #[arg(short, long, value_name = "FILE", required = true)]
+    input: Option<PathBuf>,
 
     /// Where the normalized text should be written.
-    #[arg(short, long, value_name = "FILE")]
-    output: PathBuf,
+    #[arg(short, long, value_name = "FILE", required = true)]
+    output: Option<PathBuf>,
 
     /// Optional override for the config toml. Defaults to ./config.toml.
     #[arg(short, long, value_name = "FILE")]
     config: Option<PathBuf>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print the JSON Schema of the config toml (for Taplo / Even Better TOML).
+    Schema {
+        /// Write the schema to this file instead of stdout.
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+}
+
+/// Top-level profile loaded from `config.toml`.
+#[derive(Debug, Default, Deserialize, JsonSchema)]
 #[serde(default)]
 struct Config {
     meta: MetaConfig,
@@ -58,8 +112,15 @@ struct Config {
     unicode: UnicodeConfig,
     whitespace: WhitespaceConfig,
     structure: StructureConfig,
+    pages: PageConfig,
     markdown: MarkdownConfig,
     citations: CitationConfig,
+    footnotes: FootnoteConfig,
+    back_matter: BackMatterConfig,
+    identifiers: IdentifierConfig,
+    math: MathConfig,
+    symbols: SymbolConfig,
+    charset: CharsetConfig,
     lists: ListConfig,
     abbreviations: AbbreviationConfig,
     pronunciation: PronunciationConfig,
@@ -68,28 +129,18 @@ struct Config {
     experimental: ExperimentalConfig,
     punctuation: PunctuationConfig,
     selector: SelectorConfig,
-}
-
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            meta: MetaConfig::default(),
-            io: IoConfig::default(),
-            unicode: UnicodeConfig::default(),
-            whitespace: WhitespaceConfig::default(),
-            structure: StructureConfig::default(),
-            markdown: MarkdownConfig::default(),
-            citations: CitationConfig::default(),
-            lists: ListConfig::default(),
-            abbreviations: AbbreviationConfig::default(),
-            pronunciation: PronunciationConfig::default(),
-            guardrails: GuardrailConfig::default(),
-            logging: LoggingConfig::default(),
-            experimental: ExperimentalConfig::default(),
-            punctuation: PunctuationConfig::default(),
-            selector: SelectorConfig::default(),
-        }
-    }
+    lexicons: LexiconConfig,
+    chapters: ChapterConfig,
+    pauses: PauseConfig,
+    ssml: SsmlConfig,
+    html: HtmlConfig,
+    epub: EpubConfig,
+    subtitles: SubtitleConfig,
+    transcript: TranscriptConfig,
+    latex: LatexConfig,
+    /// Inline tables merged with `lexicons.files`; filled by [`Config::load_lexicons`].
+    #[serde(skip)]
+    lexicon: Lexicon,
 }
 
 impl Config {
@@ -112,13 +163,37 @@ impl Config {
             Err(err) => Err(err).context("reading config")?,
         }
     }
+
+    /// Warn about options that old profiles may still set but that no longer do anything.
+    fn warn_deprecated(&self) {
+        if let Some(replacement) = &self.lists.bullet_replacement {
+            warn!(
+                "lists.bullet_replacement ({replacement:?}) is no longer used; set pauses.list_item instead"
+            );
+        }
+    }
+
+    /// Merge inline pronunciation tables with the external lexicon files.
+    fn load_lexicons(&mut self, base_dir: &Path) -> Result<()> {
+        self.lexicon = Lexicon::load(
+            &self.lexicons,
+            base_dir,
+            &self.pronunciation,
+            &self.abbreviations,
+        )?;
+        Ok(())
+    }
 }
 
-#[derive(Debug, Deserialize)]
+/// `[meta]`: profile bookkeeping, reported in logs and the clean report.
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(default)]
 struct MetaConfig {
+    /// Schema version of the profile.
     version: u32,
+    /// Human-readable profile name.
     profile: String,
+    /// Free-form notes about the intended pipeline.
     notes: String,
 }
 
@@ -132,17 +207,24 @@ impl Default for MetaConfig {
     }
 }
 
-#[derive(Debug, Deserialize)]
+/// `[io]`: input/output handling.
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(default)]
 struct IoConfig {
+    /// How the input file is read.
+    input_format: InputFormat,
+    /// How paragraphs are laid out in the output file.
     output_format: OutputFormat,
+    /// Convert `\r\n` and lone `\r` to `\n` before cleaning.
     normalize_line_endings: bool,
+    /// Strip trailing whitespace from every line.
     trim_trailing_whitespace: bool,
 }
 
 impl Default for IoConfig {
     fn default() -> Self {
         Self {
+            input_format: InputFormat::Auto,
             output_format: OutputFormat::OneParagraphPerLine,
             normalize_line_endings: true,
             trim_trailing_whitespace: true,
@@ -150,25 +232,119 @@ impl Default for IoConfig {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+enum InputFormat {
+    /// Pick the format from the file extension, falling back to sniffing the content.
+    #[default]
+    Auto,
+    /// Plain text or Markdown.
+    Text,
+    /// An HTML document; see `[html]`.
+    Html,
+    /// An EPUB book, read chapter by chapter; see `[epub]`.
+    Epub,
+    /// SRT or WebVTT captions; see `[subtitles]`.
+    Subtitles,
+    /// A conversation pasted from a chat UI; see `[transcript]`.
+    Transcript,
+    /// LaTeX source; see `[latex]`.
+    Latex,
+}
+
+impl InputFormat {
+    /// Resolve `auto` for the file at `path` with the given contents.
+    fn resolve(self, path: &Path, contents: &[u8]) -> Self {
+        if self != InputFormat::Auto {
+            return self;
+        }
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        // An EPUB is a zip whose first entry is the uncompressed `mimetype` file.
+        let zipped_epub = contents.starts_with(b"PK")
+            && contents
+                .get(..64)
+                .unwrap_or(contents)
+                .windows(20)
+                .any(|window| window == b"application/epub+zip");
+        if extension == "epub" || zipped_epub {
+            return InputFormat::Epub;
+        }
+        if matches!(extension.as_str(), "srt" | "vtt")
+            || subtitles::looks_like_subtitles(&String::from_utf8_lossy(
+                contents.get(..512).unwrap_or(contents),
+            ))
+        {
+            return InputFormat::Subtitles;
+        }
+        if matches!(extension.as_str(), "tex" | "latex") {
+            return InputFormat::Latex;
+        }
+        let text = String::from_utf8_lossy(contents);
+        if text.contains("\\documentclass") || text.contains("\\begin{document}") {
+            return InputFormat::Latex;
+        }
+        let head = String::from_utf8_lossy(contents.get(..64).unwrap_or(contents))
+            .trim_start()
+            .chars()
+            .take(15)
+            .collect::<String>()
+            .to_ascii_lowercase();
+        if matches!(extension.as_str(), "html" | "htm" | "xhtml")
+            || head.starts_with("<!doctype html")
+            || head.starts_with("<html")
+        {
+            InputFormat::Html
+        } else if transcript::looks_like_transcript(&text) {
+            InputFormat::Transcript
+        } else {
+            InputFormat::Text
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 enum OutputFormat {
+    /// Each paragraph on its own line.
+    #[default]
     OneParagraphPerLine,
+    /// Keep blank lines between paragraphs.
     PreserveParagraphs,
+    /// An SSML `<speak>` document; see `[ssml]`.
+    Ssml,
+}
+
+/// `[ssml]`: markup used when `io.output_format = "ssml"`.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+struct SsmlConfig {
+    /// `xml:lang` of the `<speak>` element (empty omits it).
+    lang: String,
 }
 
-impl Default for OutputFormat {
+impl Default for SsmlConfig {
     fn default() -> Self {
-        OutputFormat::OneParagraphPerLine
+        Self {
+            lang: "en-US".to_string(),
+        }
     }
 }
 
-#[derive(Debug, Deserialize)]
+/// `[unicode]`: normalization and typographic punctuation.
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(default)]
 struct UnicodeConfig {
+    /// Unicode normalization form applied to the whole input.
     normalization: UnicodeNormalizationMode,
+    /// Replace curly quotes with their ASCII equivalents.
     ascii_quotes: bool,
+    /// How em and en dashes are rendered.
     dash_mode: DashMode,
+    /// How ellipses are rendered.
     ellipsis_mode: EllipsisMode,
 }
 
@@ -177,59 +353,58 @@ impl Default for UnicodeConfig {
         Self {
             normalization: UnicodeNormalizationMode::Nfkc,
             ascii_quotes: true,
-            dash_mode: DashMode::Comma,
+            dash_mode: DashMode::Pause,
             ellipsis_mode: EllipsisMode::Period,
         }
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 enum UnicodeNormalizationMode {
+    /// Compatibility composition; folds ligatures and full-width forms.
+    #[default]
     Nfkc,
+    /// Canonical composition only.
     Nfc,
+    /// Leave the text untouched.
     None,
 }
 
-impl Default for UnicodeNormalizationMode {
-    fn default() -> Self {
-        UnicodeNormalizationMode::Nfkc
-    }
-}
-
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 enum DashMode {
-    Comma,
+    /// Replace dashes with the `pauses.dash_aside` pause (`comma` is accepted as an alias).
+    #[default]
+    #[serde(alias = "comma")]
+    Pause,
+    /// Replace dashes with a spaced hyphen.
     Hyphen,
+    /// Leave dashes untouched.
     Keep,
 }
 
-impl Default for DashMode {
-    fn default() -> Self {
-        DashMode::Comma
-    }
-}
-
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 enum EllipsisMode {
+    /// Replace ellipses with a single period.
+    #[default]
     Period,
+    /// Replace `…` with three periods.
     Triple,
+    /// Leave ellipses untouched.
     Keep,
 }
 
-impl Default for EllipsisMode {
-    fn default() -> Self {
-        EllipsisMode::Period
-    }
-}
-
-#[derive(Debug, Deserialize)]
+/// `[whitespace]`: spacing collapses.
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(default)]
 struct WhitespaceConfig {
+    /// Collapse runs of spaces, tabs and non-breaking spaces into one space.
     collapse_horizontal: bool,
+    /// Remove whitespace before `,.;:!?`.
     remove_space_before_punct: bool,
+    /// Maximum blank lines kept in a row (0 disables the collapse).
     max_consecutive_blank_lines: usize,
 }
 
@@ -243,12 +418,37 @@ impl Default for WhitespaceConfig {
     }
 }
 
-#[derive(Debug, Deserialize)]
+/// `[structure]`: paragraph detection and hard-wrap unwrapping.
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(default)]
 struct StructureConfig {
+    /// Join hard-wrapped lines inside a paragraph.
     unwrap_hard_wrapped_lines: bool,
+    /// What separates paragraphs when unwrapping.
     paragraph_boundary: ParagraphBoundary,
+    /// How line breaks inside a block are judged when unwrapping.
+    wrap_detection: WrapDetection,
+    /// Lines at least this fraction of the typical line width are always treated as wrapped.
+    wrapped_line_ratio: f32,
+    /// String inserted between joined lines.
     join_lines_with: String,
+    /// Lines that repeat one of these markers (spaces ignored) are scene breaks, realized with
+    /// the `pauses.scene_break` pause; an empty list disables detection.
+    #[serde(default = "StructureConfig::default_scene_break_markers")]
+    scene_break_markers: Vec<String>,
+    /// Rejoin words hyphenated across line ends ("inter-" / "national") before unwrapping.
+    dehyphenate: bool,
+    /// Hyphenated words to keep as written when split at their hyphen, on top of the
+    /// built-in compound lists and the spellings found in the document.
+    hyphenated_compounds: Vec<String>,
+}
+
+impl StructureConfig {
+    fn default_scene_break_markers() -> Vec<String> {
+        ["***", "---", "___", "~~~", "#", "§", "⁂"]
+            .map(str::to_string)
+            .to_vec()
+    }
 }
 
 impl Default for StructureConfig {
@@ -256,30 +456,51 @@ impl Default for StructureConfig {
         Self {
             unwrap_hard_wrapped_lines: true,
             paragraph_boundary: ParagraphBoundary::BlankLines,
+            wrap_detection: WrapDetection::Auto,
+            wrapped_line_ratio: 0.6,
             join_lines_with: " ".to_string(),
+            scene_break_markers: Self::default_scene_break_markers(),
+            dehyphenate: true,
+            hyphenated_compounds: Vec::new(),
         }
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 enum ParagraphBoundary {
+    /// Blank lines end a paragraph.
+    #[default]
     BlankLines,
+    /// Blank lines and lines indented deeper than the one before start a paragraph
+    /// (PDF and Gutenberg texts).
+    IndentedFirstLine,
+    /// Join the whole block into one paragraph.
     Never,
 }
 
-impl Default for ParagraphBoundary {
-    fn default() -> Self {
-        ParagraphBoundary::BlankLines
-    }
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+enum WrapDetection {
+    /// Keep line breaks that look intentional (short lines, closing punctuation, a capital
+    /// or a change of indentation on the next line) and join the rest.
+    #[default]
+    Auto,
+    /// Join every line of a block.
+    JoinAll,
 }
 
-#[derive(Debug, Deserialize)]
+/// `[markdown]`: Markdown markup removal.
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(default)]
 struct MarkdownConfig {
+    /// Remove fenced code blocks.
     drop_code_fences: bool,
+    /// Text spoken in place of a dropped code fence.
     code_fence_replacement: String,
+    /// Keep the contents of inline code spans but drop the backticks.
     strip_inline_code: bool,
+    /// Replace `[text](url)` with `text`.
     strip_markdown_links: bool,
 }
 
@@ -294,35 +515,49 @@ impl Default for MarkdownConfig {
     }
 }
 
-#[derive(Debug, Deserialize)]
+/// `[citations]`: citation and reference-marker removal.
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(default)]
 struct CitationConfig {
+    /// Drop author–year citations such as `(Lie & Bos, 1999)`; "Meyer (2004)" keeps the name.
+    drop_author_year: bool,
+    /// Drop numeric citations such as `[12]`.
     drop_numeric_brackets: bool,
+    /// Drop stacked numeric citations such as `[1][2]`.
     drop_stacked_numeric_brackets: bool,
+    /// Drop parenthesized numeric citations such as `(1, 2)`.
     drop_parenthetical_numeric: bool,
+    /// Drop any parenthetical that contains a digit.
     drop_generic_parentheses: bool,
+    /// Drop any bracketed span that contains a digit.
     drop_generic_brackets: bool,
 }
 
 impl Default for CitationConfig {
     fn default() -> Self {
         Self {
+            drop_author_year: true,
             drop_numeric_brackets: true,
             drop_stacked_numeric_brackets: true,
             drop_parenthetical_numeric: false,
-            drop_generic_parentheses: true,
+            drop_generic_parentheses: false,
             drop_generic_brackets: true,
         }
     }
 }
 
-#[derive(Debug, Deserialize)]
+/// `[lists]`: bullet flattening.
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(default)]
 struct ListConfig {
+    /// Drop leading bullet markers and separate items with the `pauses.list_item` pause.
     flatten_bullets: bool,
-    bullet_replacement: String,
+    /// Line prefixes treated as bullet markers.
     #[serde(default = "ListConfig::default_markers")]
     bullet_markers: Vec<String>,
+    /// No longer used; list items are paced by `pauses.list_item`. Kept so old profiles warn.
+    #[schemars(skip)]
+    bullet_replacement: Option<String>,
 }
 
 impl ListConfig {
@@ -335,21 +570,37 @@ impl Default for ListConfig {
     fn default() -> Self {
         Self {
             flatten_bullets: true,
-            bullet_replacement: ", ".to_string(),
             bullet_markers: Self::default_markers(),
+            bullet_replacement: None,
         }
     }
 }
 
-#[derive(Debug, Deserialize)]
+/// `[abbreviations]`: acronym spelling.
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(default)]
 struct AbbreviationConfig {
+    /// Spell out the acronyms listed in `tokens`.
     expand_acronyms: bool,
+    /// Acronyms that are spelled letter by letter.
     #[serde(default)]
     tokens: Vec<String>,
+    /// Spoken form of each letter and digit.
     letter_sounds: BTreeMap<String, String>,
+    /// Separator between spelled letters.
     letter_separator: String,
+    /// Separator between dotted digit groups that follow an acronym.
     digit_separator: String,
+    /// Which capitalizations of a token are expanded; `all-caps` keeps "US" from rewriting "us".
+    case: CaseMatch,
+    /// Spell or keep all-caps tokens missing from `tokens`; when off they are only reported.
+    detect_unknown: bool,
+    /// Minimum letters for a detected token (shorter tokens are left alone).
+    detect_min_len: usize,
+    /// Acronyms read as words (NASA, NATO) instead of letter by letter.
+    pronounceable: Vec<String>,
+    /// Custom respellings per acronym (`SQL = "sequel"`); these win over `pronounceable`.
+    map: BTreeMap<String, String>,
 }
 
 impl Default for AbbreviationConfig {
@@ -417,22 +668,45 @@ impl Default for AbbreviationConfig {
             letter_sounds,
             letter_separator: ". ".to_string(),
             digit_separator: " dot ".to_string(),
+            case: CaseMatch::AllCaps,
+            detect_unknown: false,
+            detect_min_len: 2,
+            pronounceable: [
+                "NASA", "NATO", "SCSI", "GIF", "JPEG", "LASER", "RADAR", "SCUBA", "UNESCO",
+                "UNICEF", "FIFA", "AIDS", "COVID", "OPEC", "ASCII", "WYSIWYG", "CAPTCHA", "PIN",
+                "RAM", "ROM", "SIM", "LAN", "WAN", "GUI", "POSIX", "NVIDIA", "DOM",
+            ]
+            .map(str::to_string)
+            .to_vec(),
+            map: BTreeMap::new(),
         }
     }
 }
 
-#[derive(Debug, Deserialize)]
+/// `[pronunciation]`: replacements and spoken forms.
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(default)]
 struct PronunciationConfig {
+    /// Apply the literal `replacements` table.
     enable_replacements: bool,
+    /// Literal string replacements, longest key first.
     #[serde(default)]
     replacements: BTreeMap<String, String>,
+    /// Whole-word brand spellings such as `MySQL`.
     #[serde(default)]
     brand_map: BTreeMap<String, String>,
+    /// Which capitalizations of a `brand_map` key (or a brand lexicon entry without its own
+    /// `case`) are rewritten; it does not apply to `replacements` or anything else here.
+    case: CaseMatch,
+    /// How four-digit years are read.
     year_mode: YearMode,
+    /// Speak opening HTML tag names and drop closing tags.
     html_tag_pronunciation: bool,
+    /// Text appended after a spoken tag name.
     html_tag_separator: String,
+    /// How dotted version numbers are read.
     version_mode: VersionMode,
+    /// Spelled-number formatting.
     number_config: NumberConfig,
 }
 
@@ -444,8 +718,6 @@ impl Default for PronunciationConfig {
         replacements.insert(";".to_string(), ",".to_string());
         replacements.insert("{".to_string(), " brace ".to_string());
         replacements.insert("}".to_string(), " brace ".to_string());
-        replacements.insert("(".to_string(), ", ".to_string());
-        replacements.insert(")".to_string(), ", ".to_string());
 
         let mut brand_map = BTreeMap::new();
         brand_map.insert("MySQL".to_string(), "My S. Q. L.".to_string());
@@ -461,6 +733,7 @@ impl Default for PronunciationConfig {
             enable_replacements: true,
             replacements,
             brand_map,
+            case: CaseMatch::Insensitive,
             year_mode: YearMode::American,
             html_tag_pronunciation: true,
             html_tag_separator: " ".to_string(),
@@ -470,10 +743,13 @@ impl Default for PronunciationConfig {
     }
 }
 
-#[derive(Debug, Deserialize)]
+/// Spelled-number formatting shared by the year and version stages.
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(default)]
 struct NumberConfig {
+    /// Separator between spelled number groups.
     separator: String,
+    /// Insert "and" before the tens of a year ("nineteen hundred and two").
     insert_and: bool,
 }
 
@@ -486,36 +762,33 @@ impl Default for NumberConfig {
     }
 }
 
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 enum VersionMode {
+    /// Leave version numbers untouched.
     None,
+    /// Read `2.3.4` as "two point three point four".
+    #[default]
     SayDecimal,
 }
 
-impl Default for VersionMode {
-    fn default() -> Self {
-        VersionMode::SayDecimal
-    }
-}
-
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 enum YearMode {
+    /// Leave years untouched.
     None,
+    /// Read years as spelled numbers.
+    #[default]
     American,
 }
 
-impl Default for YearMode {
-    fn default() -> Self {
-        YearMode::American
-    }
-}
-
-#[derive(Debug, Deserialize)]
+/// `[guardrails]`: warning thresholds.
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(default)]
 struct GuardrailConfig {
+    /// Warn when the output is shorter than this many chars (0 disables).
     min_output_chars_warn: usize,
+    /// Warn when a paragraph is longer than this many chars (0 disables).
     max_paragraph_chars: usize,
 }
 
@@ -528,12 +801,17 @@ impl Default for GuardrailConfig {
     }
 }
 
-#[derive(Debug, Deserialize)]
+/// `[logging]`: log level and report output.
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(default)]
 struct LoggingConfig {
+    /// Default log filter when `RUST_LOG` is unset.
     level: String,
+    /// Log a byte and paragraph summary after cleaning.
     print_summary: bool,
+    /// Write a plain-text report to `report_path`.
     write_report: bool,
+    /// Where the clean report is written.
     report_path: String,
 }
 
@@ -548,10 +826,13 @@ impl Default for LoggingConfig {
     }
 }
 
-#[derive(Debug, Deserialize)]
+/// `[experimental]`: opt-in stages that are still being tuned.
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(default)]
 struct ExperimentalConfig {
+    /// Collapse long runs of `=`, `\`, `-` or `~` into one character.
     strip_punct_runs: bool,
+    /// Minimum run length collapsed by `strip_punct_runs`.
     punct_run_min_len: usize,
 }
 
@@ -564,12 +845,17 @@ impl Default for ExperimentalConfig {
     }
 }
 
-#[derive(Debug, Deserialize)]
+/// `[punctuation]`: punctuation clean-up.
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(default)]
 struct PunctuationConfig {
+    /// Collapse runs of commas.
     collapse_commas: bool,
+    /// Commas kept in a row when collapsing.
     max_consecutive_commas: usize,
+    /// Text spoken in place of `/` (empty drops it).
     slash_replacement: String,
+    /// Stop characters, strongest first, used to collapse runs like `,.`.
     stop_precedence: String,
 }
 
@@ -584,9 +870,11 @@ impl Default for PunctuationConfig {
     }
 }
 
-#[derive(Debug, Deserialize)]
+/// `[selector]`: CSS class selector pronunciation.
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(default)]
 struct SelectorConfig {
+    /// Text spoken in place of the leading dot of `.class` selectors.
     prefix: String,
 }
 
@@ -603,19 +891,217 @@ struct CleanStats {
     input_length: usize,
     output_length: usize,
     paragraph_count: usize,
+    scene_breaks: usize,
+    dehyphenated: usize,
+    kept_line_breaks: usize,
+    identifiers: usize,
+    math_expressions: usize,
+    symbols: usize,
+    transliterated: usize,
+    unsupported_letters: BTreeMap<char, UnsupportedLetter>,
+    detected_acronyms: BTreeMap<String, DetectedAcronym>,
+}
+
+impl CleanStats {
+    /// Fold the stats of another chapter into these.
+    fn absorb(&mut self, other: CleanStats) {
+        self.input_length += other.input_length;
+        self.output_length += other.output_length;
+        self.paragraph_count += other.paragraph_count;
+        self.scene_breaks += other.scene_breaks;
+        self.dehyphenated += other.dehyphenated;
+        self.kept_line_breaks += other.kept_line_breaks;
+        self.identifiers += other.identifiers;
+        self.math_expressions += other.math_expressions;
+        self.symbols += other.symbols;
+        self.transliterated += other.transliterated;
+        for (token, found) in other.detected_acronyms {
+            self.detected_acronyms
+                .entry(token)
+                .and_modify(|existing| existing.count += found.count)
+                .or_insert(found);
+        }
+    }
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
-    let config = Config::load(args.config.as_deref())?;
+    if let Some(Command::Schema { output }) = &args.command {
+        return write_schema(output.as_deref());
+    }
+    // `required = true` makes clap reject a run without a subcommand that lacks either path.
+    let (Some(input), Some(output)) = (args.input.as_deref(), args.output.as_deref()) else {
+        unreachable!("clap enforces --input and --output");
+    };
+
+    let mut config = Config::load(args.config.as_deref())?;
     init_logger(&config.logging);
     info!("Loaded config profile: {}", config.meta.profile);
+    config.warn_deprecated();
+    let config_dir = args
+        .config
+        .as_deref()
+        .and_then(Path::parent)
+        .unwrap_or_else(|| Path::new(""));
+    config.load_lexicons(config_dir)?;
+
+    let bytes = fs::read(input).with_context(|| format!("Failed to read {}", input.display()))?;
+    info!("Read {} bytes from {}", bytes.len(), input.display());
+    let input_length = bytes.len();
+
+    let input_format = config.io.input_format.resolve(input, &bytes);
+
+    // The input as documents: the spine of an EPUB, the turns of a transcript in `segments`
+    // mode, otherwise one untitled document. Captions skip the document stages: there are
+    // no pages, notes or chapters in them.
+    // Letters outside the target alphabet are found before any stage rewrites the text, so
+    // their positions can be looked up in the input file (or, in an EPUB, in its chapters).
+    let mut captions = None;
+    let mut unsupported_letters = BTreeMap::new();
+    let names_greek = config.math.names_greek_letters();
+    let documents = match input_format {
+        InputFormat::Epub => {
+            let documents = epub::read(&bytes, &config.epub, &config.html)
+                .with_context(|| format!("Failed to read EPUB {}", input.display()))?;
+            for (idx, document) in documents.iter().enumerate() {
+                let name = document
+                    .title
+                    .clone()
+                    .unwrap_or_else(|| format!("document {}", idx + 1));
+                charset::scan(
+                    &document.text,
+                    Some(&name),
+                    &config.charset,
+                    names_greek,
+                    &mut unsupported_letters,
+                );
+            }
+            documents
+        }
+        _ => {
+            let raw = String::from_utf8(bytes)
+                .with_context(|| format!("{} is not valid UTF-8", input.display()))?;
+            charset::scan(
+                &raw,
+                None,
+                &config.charset,
+                names_greek,
+                &mut unsupported_letters,
+            );
+            match input_format {
+                InputFormat::Subtitles => {
+                    captions = Some(raw);
+                    Vec::new()
+                }
+                InputFormat::Html => {
+                    let text = html::to_text(&raw, &config.html);
+                    info!("Extracted {} bytes of text from HTML", text.len());
+                    vec![Chapter { title: None, text }]
+                }
+                InputFormat::Latex => {
+                    let text = latex::to_text(&raw, &config.latex);
+                    info!("Extracted {} bytes of text from LaTeX", text.len());
+                    vec![Chapter { title: None, text }]
+                }
+                InputFormat::Transcript => {
+                    let bullet = config
+                        .lists
+                        .bullet_markers
+                        .first()
+                        .map_or("- ", String::as_str);
+                    transcript::to_documents(&raw, &config.transcript, bullet)
+                }
+                _ => vec![Chapter {
+                    title: None,
+                    text: raw,
+                }],
+            }
+        }
+    };
 
-    let raw = fs::read_to_string(&args.input)
-        .with_context(|| format!("Failed to read {}", args.input.display()))?;
-    info!("Read {} bytes from {}", raw.len(), args.input.display());
+    let mut page_stats = PageStats::default();
+    let mut note_count = 0;
+    let mut back_matter = Vec::new();
+    let mut chapters = Vec::new();
+    for document in documents {
+        let (raw, document_pages) = pages::strip_page_furniture(&document.text, &config.pages);
+        page_stats.absorb(document_pages);
+        let (raw, notes) = footnotes::extract(&raw, &config.footnotes, &config.chapters);
+        note_count += notes.len();
+        let (raw, removed) = backmatter::strip(&raw, &config.back_matter, &config.chapters);
+        back_matter.extend(removed);
+        let mut document_chapters = chapters::split(&raw, &config.chapters);
+        // An EPUB document stays one chapter, named by its TOC entry; splitting it only
+        // normalizes its headings.
+        if let Some(title) = document.title {
+            let text = document_chapters
+                .iter()
+                .map(|chapter| chapter.text.as_str())
+                .collect::<Vec<_>>()
+                .join("\n");
+            document_chapters = if text.trim().is_empty() {
+                Vec::new()
+            } else {
+                vec![Chapter {
+                    title: Some(title),
+                    text,
+                }]
+            };
+        }
+        for chapter in &mut document_chapters {
+            chapter.text = footnotes::place(&chapter.text, &notes, &config.footnotes);
+        }
+        chapters.extend(document_chapters);
+    }
+    if page_stats.running_heads + page_stats.page_numbers + page_stats.page_breaks > 0 {
+        info!(
+            "Removed {} running headers or footers, {} page numbers and {} page breaks",
+            page_stats.running_heads, page_stats.page_numbers, page_stats.page_breaks
+        );
+    }
+    if note_count > 0 {
+        info!("Found {note_count} footnotes");
+    }
+    for section in &back_matter {
+        info!(
+            "Removed back matter \"{}\" ({} entries)",
+            section.heading, section.entries
+        );
+    }
+    if chapters.len() > 1 {
+        info!("Detected {} chapters", chapters.len());
+    }
+    let split_output = captions.is_none()
+        && (config.chapters.split_output()
+            || (input_format == InputFormat::Epub && config.epub.split_output())
+            || (input_format == InputFormat::Transcript && config.transcript.segments()));
 
-    let (cleaned, stats) = clean_text(&raw, &config);
+    let mut stats = CleanStats::default();
+    let mut cleaned_chapters = Vec::new();
+    if let Some(raw) = &captions {
+        let (cleaned, caption_stats) = subtitles::clean(raw, &config)
+            .with_context(|| format!("Failed to read subtitles {}", input.display()))?;
+        stats.absorb(caption_stats);
+        cleaned_chapters.push(cleaned);
+    } else if split_output {
+        for chapter in &chapters {
+            let (cleaned, chapter_stats) = clean_text(&chapter.text, &config);
+            stats.absorb(chapter_stats);
+            cleaned_chapters.push(cleaned);
+        }
+    } else {
+        let joined = chapters
+            .iter()
+            .map(|chapter| chapter.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let (cleaned, all_stats) = clean_text(&joined, &config);
+        stats.absorb(all_stats);
+        cleaned_chapters.push(cleaned);
+    }
+    // The summary counts the input as read, not what is left after the document stages.
+    stats.input_length = input_length;
+    stats.unsupported_letters = unsupported_letters;
     info!(
         "Cleaned text is {} bytes ({} paragraphs)",
         stats.output_length, stats.paragraph_count
@@ -636,27 +1122,139 @@ fn main() -> Result<()> {
             stats.input_length, stats.output_length, stats.paragraph_count
         );
     }
+    if stats.dehyphenated > 0 {
+        info!("Rejoined {} words split across lines", stats.dehyphenated);
+    }
+    if stats.kept_line_breaks > 0 {
+        info!(
+            "Kept {} line breaks that look intentional",
+            stats.kept_line_breaks
+        );
+    }
+    if stats.identifiers > 0 {
+        info!(
+            "Handled {} DOIs, ISBNs and similar identifiers",
+            stats.identifiers
+        );
+    }
+    if stats.math_expressions > 0 {
+        info!(
+            "Read {} math expressions and symbols in words",
+            stats.math_expressions
+        );
+    }
+    if stats.symbols > 0 {
+        info!("Replaced {} symbols and emoji", stats.symbols);
+    }
+    if !stats.unsupported_letters.is_empty() {
+        warn!(
+            "Found {} letters {}: {}",
+            stats
+                .unsupported_letters
+                .values()
+                .map(|found| found.count)
+                .sum::<usize>(),
+            charset::describe(&config.charset),
+            charset::format_unsupported(&stats.unsupported_letters, &config.charset, ", ")
+        );
+    }
+    if stats.scene_breaks > 0 {
+        info!("Replaced {} scene breaks", stats.scene_breaks);
+    }
+    if !stats.detected_acronyms.is_empty() {
+        info!(
+            "Detected {} unknown acronyms: {}",
+            stats.detected_acronyms.len(),
+            format_detected_acronyms(&stats.detected_acronyms, ", ")
+        );
+    }
 
     if config.logging.write_report {
-        let report = format!(
-            "Clean report\n============\nInput: {}\nOutput: {}\nParagraphs: {}\nProfile: {}\n",
-            args.input.display(),
-            args.output.display(),
+        let mut report = format!(
+            "Clean report\n============\nInput: {}\nOutput: {}\nParagraphs: {}\nChapters: {}\nFootnotes: {}\nScene breaks: {}\nRunning headers/footers removed: {}\nPage numbers removed: {}\nPage breaks removed: {}\nProfile: {}\n",
+            input.display(),
+            output.display(),
             stats.paragraph_count,
+            chapters.len(),
+            note_count,
+            stats.scene_breaks,
+            page_stats.running_heads,
+            page_stats.page_numbers,
+            page_stats.page_breaks,
             config.meta.profile
         );
+        if !back_matter.is_empty() {
+            report.push_str("\nBack matter removed\n-------------------\n");
+            for section in &back_matter {
+                report.push_str(&format!(
+                    "{} ({} entries)\n",
+                    section.heading, section.entries
+                ));
+            }
+        }
+        if !stats.detected_acronyms.is_empty() {
+            report.push_str("\nDetected acronyms\n-----------------\n");
+            report.push_str(&format_detected_acronyms(&stats.detected_acronyms, "\n"));
+            report.push('\n');
+        }
+        if !stats.unsupported_letters.is_empty() {
+            report.push_str("\nUnsupported letters\n-------------------\n");
+            report.push_str(&charset::format_unsupported(
+                &stats.unsupported_letters,
+                &config.charset,
+                "\n",
+            ));
+            report.push('\n');
+        }
         fs::write(&config.logging.report_path, report)
             .with_context(|| format!("writing report to {}", config.logging.report_path))?;
         info!("Wrote report to {}", config.logging.report_path);
     }
 
-    fs::write(&args.output, cleaned)
-        .with_context(|| format!("Failed to write {}", args.output.display()))?;
-    info!("Wrote cleaned text to {}", args.output.display());
+    if split_output {
+        chapters::write_chapters(
+            output,
+            input,
+            &chapters,
+            &cleaned_chapters,
+            &config.chapters,
+        )?;
+    } else {
+        let cleaned = &cleaned_chapters[0];
+        fs::write(output, cleaned)
+            .with_context(|| format!("Failed to write {}", output.display()))?;
+        info!("Wrote cleaned text to {}", output.display());
+    }
+
+    Ok(())
+}
 
+/// Emit the JSON Schema generated from the config structs, so it always matches the binary.
+fn write_schema(output: Option<&Path>) -> Result<()> {
+    let schema = schemars::schema_for!(Config);
+    let mut json = serde_json::to_string_pretty(&schema).context("serializing config schema")?;
+    json.push('\n');
+    match output {
+        Some(path) => {
+            fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))?
+        }
+        None => print!("{json}"),
+    }
     Ok(())
 }
 
+/// Render detector decisions as `TOKEN: decision (count)` entries.
+fn format_detected_acronyms(
+    detected: &BTreeMap<String, DetectedAcronym>,
+    separator: &str,
+) -> String {
+    detected
+        .iter()
+        .map(|(token, found)| format!("{}: {:?} ({})", token, found.decision, found.count))
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
 fn init_logger(logging: &LoggingConfig) {
     let env = env_logger::Env::default().default_filter_or(logging.level.as_str());
     Builder::from_env(env).init();
@@ -664,8 +1262,11 @@ fn init_logger(logging: &LoggingConfig) {
 
 fn clean_text(s: &str, config: &Config) -> (String, CleanStats) {
     let mut text = s.to_string();
-    let mut stats = CleanStats::default();
-    stats.input_length = text.len();
+    let mut stats = CleanStats {
+        input_length: text.len(),
+        ..Default::default()
+    };
+    let ssml = matches!(config.io.output_format, OutputFormat::Ssml);
 
     if config.io.normalize_line_endings {
         text = text.replace("\r\n", "\n").replace('\r', "\n");
@@ -679,6 +1280,25 @@ fn clean_text(s: &str, config: &Config) -> (String, CleanStats) {
             .join("\n");
     }
 
+    text = wrap::strip_soft_hyphens(&text);
+
+    if config.structure.unwrap_hard_wrapped_lines {
+        let (marked, kept) = wrap::separate_intentional_breaks(
+            &text,
+            &config.structure,
+            &config.lists.bullet_markers,
+        );
+        text = marked;
+        stats.kept_line_breaks = kept;
+    }
+
+    let (spoken, count) = math::verbalize(&text, &config.math);
+    text = spoken;
+    stats.math_expressions = count;
+    let (spoken, count) = symbols::verbalize(&text, &config.symbols, &config.pauses);
+    text = spoken;
+    stats.symbols = count;
+
     text = match config.unicode.normalization {
         UnicodeNormalizationMode::Nfkc => text.nfkc().collect::<String>(),
         UnicodeNormalizationMode::Nfc => text.nfc().collect::<String>(),
@@ -694,8 +1314,8 @@ fn clean_text(s: &str, config: &Config) -> (String, CleanStats) {
     }
 
     text = match config.unicode.dash_mode {
-        DashMode::Comma => text.replace('—', ", ").replace('–', ", "),
-        DashMode::Hyphen => text.replace('—', " - ").replace('–', " - "),
+        DashMode::Pause => text.replace(['—', '–'], &config.pauses.inline(PauseEvent::DashAside)),
+        DashMode::Hyphen => text.replace(['—', '–'], " - "),
         DashMode::Keep => text,
     };
 
@@ -705,12 +1325,14 @@ fn clean_text(s: &str, config: &Config) -> (String, CleanStats) {
         EllipsisMode::Keep => text,
     };
 
-    if config.markdown.drop_code_fences {
-        if RE_CODE_FENCE.is_match(&text) {
-            text = RE_CODE_FENCE
-                .replace_all(&text, config.markdown.code_fence_replacement.as_str())
-                .to_string();
-        }
+    let (transliterated, count) = charset::apply(&text, &config.charset);
+    text = transliterated;
+    stats.transliterated = count;
+
+    if config.markdown.drop_code_fences && RE_CODE_FENCE.is_match(&text) {
+        text = RE_CODE_FENCE
+            .replace_all(&text, config.markdown.code_fence_replacement.as_str())
+            .to_string();
     }
 
     if config.markdown.strip_inline_code {
@@ -721,6 +1343,13 @@ fn clean_text(s: &str, config: &Config) -> (String, CleanStats) {
         text = RE_MARKDOWN_LINK.replace_all(&text, "$1").to_string();
     }
 
+    let (handled, count) = identifiers::handle_identifiers(&text, &config.identifiers);
+    text = handled;
+    stats.identifiers = count;
+
+    if config.citations.drop_author_year {
+        text = citations::drop_author_year(&text);
+    }
     if config.citations.drop_stacked_numeric_brackets {
         text = RE_STACKED_NUM_CITE.replace_all(&text, "").to_string();
     }
@@ -737,6 +1366,24 @@ fn clean_text(s: &str, config: &Config) -> (String, CleanStats) {
         text = RE_GENERIC_PARENS.replace_all(&text, "").to_string();
     }
 
+    if config.structure.dehyphenate {
+        let (repaired, count) = wrap::dehyphenate(&text, &config.structure.hyphenated_compounds);
+        text = repaired;
+        stats.dehyphenated = count;
+    }
+
+    text = pauses::mark_headings_and_scene_breaks(
+        &text,
+        &config.pauses,
+        ssml,
+        &config.structure.scene_break_markers,
+        &mut stats.scene_breaks,
+    );
+    if config.lists.flatten_bullets {
+        text = flatten_bullets(&text, &config.lists, &config.pauses, ssml);
+    }
+    text = pauses::realize_parentheticals(&text, &config.pauses);
+
     if config.structure.unwrap_hard_wrapped_lines {
         text = unwrap_paragraphs(
             &text,
@@ -745,10 +1392,6 @@ fn clean_text(s: &str, config: &Config) -> (String, CleanStats) {
         );
     }
 
-    if config.lists.flatten_bullets {
-        text = flatten_bullets(&text, &config.lists);
-    }
-
     if config.whitespace.collapse_horizontal {
         text = RE_MULTI_SPACE.replace_all(&text, " ").to_string();
     }
@@ -761,49 +1404,79 @@ fn clean_text(s: &str, config: &Config) -> (String, CleanStats) {
         text = collapse_blank_lines(&text, config.whitespace.max_consecutive_blank_lines);
     }
 
-    if config.pronunciation.enable_replacements && !config.pronunciation.replacements.is_empty() {
-        text = apply_replacements(&text, &config.pronunciation.replacements);
+    if config.pronunciation.enable_replacements && !config.lexicon.replacements.is_empty() {
+        text = apply_replacements(&text, &config.lexicon.replacements);
     }
-    if !config.pronunciation.brand_map.is_empty() {
-        text = apply_brand_pronunciation(&text, &config.pronunciation.brand_map);
+    if !config.lexicon.brands.is_empty() {
+        text = apply_brand_pronunciation(&text, &config.lexicon.brands, ssml);
     }
 
+    // Brands and acronyms may have marked spans by now; later stages only see the rest.
     match config.pronunciation.year_mode {
         YearMode::American => {
-            text = apply_year_pronunciation(
-                &text,
-                &config.pronunciation.year_mode,
-                &config.pronunciation.number_config,
-            );
+            text = ssml::map_unmarked(&text, |plain| {
+                apply_year_pronunciation(
+                    plain,
+                    &config.pronunciation.year_mode,
+                    &config.pronunciation.number_config,
+                )
+            });
         }
         YearMode::None => {}
     }
 
-    if config.abbreviations.expand_acronyms && !config.abbreviations.tokens.is_empty() {
-        text = expand_acronyms(&text, &config.abbreviations);
+    if config.abbreviations.expand_acronyms && !config.lexicon.acronyms.is_empty() {
+        text = expand_acronyms(&text, &config.abbreviations, &config.lexicon, ssml);
+    }
+    if config.abbreviations.expand_acronyms {
+        text = expand_unknown_acronyms(
+            &text,
+            &config.abbreviations,
+            &config.lexicon,
+            &mut stats.detected_acronyms,
+            ssml,
+        );
     }
 
     if config.pronunciation.version_mode != VersionMode::None {
-        text = apply_version_pronunciation(&text, &config.pronunciation.version_mode);
+        text = ssml::map_unmarked(&text, |plain| {
+            apply_version_pronunciation(plain, &config.pronunciation.version_mode)
+        });
     }
 
     if config.pronunciation.html_tag_pronunciation {
-        text = apply_html_pronunciation(&text, &config.pronunciation.html_tag_separator);
+        text = ssml::map_unmarked(&text, |plain| {
+            apply_html_pronunciation(plain, &config.pronunciation.html_tag_separator)
+        });
     }
     if !config.selector.prefix.is_empty() {
-        text = apply_selector_pronunciation(&text, &config.selector.prefix);
+        text = ssml::map_unmarked(&text, |plain| {
+            apply_selector_pronunciation(plain, &config.selector.prefix)
+        });
     }
 
     if config.punctuation.collapse_commas && config.punctuation.max_consecutive_commas > 0 {
-        text = collapse_commas(&text, config.punctuation.max_consecutive_commas);
+        text = ssml::map_unmarked(&text, |plain| {
+            collapse_commas(plain, config.punctuation.max_consecutive_commas)
+        });
     }
 
     if !config.punctuation.slash_replacement.is_empty() {
-        text = replace_slashes(&text, &config.punctuation.slash_replacement);
+        text = ssml::map_unmarked(&text, |plain| {
+            replace_slashes(plain, &config.punctuation.slash_replacement)
+        });
     }
 
     if !config.punctuation.stop_precedence.is_empty() {
-        text = collapse_stop_sequences(&text, &config.punctuation.stop_precedence);
+        text = ssml::map_unmarked(&text, |plain| {
+            collapse_stop_sequences(plain, &config.punctuation.stop_precedence)
+        });
+    }
+
+    text = pauses::close_paragraphs(&text, &config.pauses);
+    if !ssml {
+        text = config.pauses.expand_markers(&text);
+        text = RE_SPACE_BEFORE_PUNCT.replace_all(&text, "$1").to_string();
     }
 
     text = RE_COMMA_BEFORE_PERIOD.replace_all(&text, ".").to_string();
@@ -811,12 +1484,13 @@ fn clean_text(s: &str, config: &Config) -> (String, CleanStats) {
     text = RE_MULTI_SPACE.replace_all(&text, " ").to_string();
 
     if config.experimental.strip_punct_runs && config.experimental.punct_run_min_len > 0 {
-        let pattern = RE_PUNCT_RUN.clone();
-        text = pattern
-            .replace_all(&text, |caps: &regex::Captures| {
-                caps[1].chars().next().unwrap_or('-').to_string()
-            })
-            .to_string();
+        text = ssml::map_unmarked(&text, |plain| {
+            RE_PUNCT_RUN
+                .replace_all(plain, |caps: &regex::Captures| {
+                    caps[1].chars().next().unwrap_or('-').to_string()
+                })
+                .to_string()
+        });
     }
 
     if config.io.trim_trailing_whitespace {
@@ -830,9 +1504,9 @@ fn clean_text(s: &str, config: &Config) -> (String, CleanStats) {
     text = text.trim().to_string();
     text.push('\n');
 
-    stats.output_length = text.len();
     stats.paragraph_count = text.lines().filter(|line| !line.trim().is_empty()).count();
 
+    // Measured on the paragraphs themselves, before SSML wraps them in markup.
     if config.guardrails.max_paragraph_chars > 0 {
         let limit = config.guardrails.max_paragraph_chars;
         for line in text.lines() {
@@ -846,6 +1520,11 @@ fn clean_text(s: &str, config: &Config) -> (String, CleanStats) {
         }
     }
 
+    if ssml {
+        text = ssml::render(&text, &config.ssml, &config.pauses);
+    }
+    stats.output_length = text.len();
+
     (text, stats)
 }
 
@@ -859,7 +1538,7 @@ fn unwrap_paragraphs(text: &str, joiner: &str, boundary: &ParagraphBoundary) ->
                 paragraphs.push(buffer.join(joiner));
                 buffer.clear();
             }
-            if let ParagraphBoundary::BlankLines = boundary {
+            if !matches!(boundary, ParagraphBoundary::Never) {
                 paragraphs.push(String::new());
             }
         } else {
@@ -874,20 +1553,26 @@ fn unwrap_paragraphs(text: &str, joiner: &str, boundary: &ParagraphBoundary) ->
     paragraphs.join("\n")
 }
 
-fn flatten_bullets(text: &str, cfg: &ListConfig) -> String {
-    text.lines()
-        .map(|line| {
-            let trimmed = line.trim_start();
-            if let Some(marker) = cfg
-                .bullet_markers
-                .iter()
-                .find(|marker| trimmed.starts_with(marker.as_str()))
-            {
-                let remainder = trimmed[marker.len()..].trim_start();
-                format!("{}{}", cfg.bullet_replacement, remainder)
-            } else {
-                line.to_string()
+/// Drop bullet markers; every item but the last in a run is closed with the `list_item`
+/// pause so the joined list reads as an enumeration.
+fn flatten_bullets(text: &str, cfg: &ListConfig, pauses: &PauseConfig, ssml: bool) -> String {
+    let item = |line: &str| {
+        let trimmed = line.trim_start();
+        cfg.bullet_markers
+            .iter()
+            .find(|marker| trimmed.starts_with(marker.as_str()))
+            .map(|marker| trimmed[marker.len()..].trim_start().to_string())
+    };
+    let lines: Vec<&str> = text.lines().collect();
+    lines
+        .iter()
+        .enumerate()
+        .map(|(idx, line)| match item(line) {
+            Some(remainder) if lines.get(idx + 1).is_some_and(|next| item(next).is_some()) => {
+                pauses.close(&remainder, PauseEvent::ListItem, ssml)
             }
+            Some(remainder) => remainder,
+            None => line.to_string(),
         })
         .collect::<Vec<_>>()
         .join("\n")
@@ -975,10 +1660,10 @@ fn collapse_stop_sequences(text: &str, precedence: &str) -> String {
         }
         result.push(ch);
     }
-    if !run.is_empty() {
-        if let Some(chosen) = choose_stop(&run, precedence) {
-            result.push(chosen);
-        }
+    if !run.is_empty()
+        && let Some(chosen) = choose_stop(&run, precedence)
+    {
+        result.push(chosen);
     }
     result
 }
@@ -992,26 +1677,29 @@ fn choose_stop(run: &[char], precedence: &str) -> Option<char> {
     run.first().copied()
 }
 
-fn apply_replacements(text: &str, replacements: &BTreeMap<String, String>) -> String {
+fn apply_replacements(text: &str, replacements: &[LexiconEntry]) -> String {
     let mut result = text.to_string();
     let mut entries: Vec<_> = replacements.iter().collect();
-    entries.sort_by_key(|(key, _)| Reverse(key.len()));
+    entries.sort_by_key(|entry| Reverse(entry.term.len()));
 
-    for (from, to) in entries {
-        result = result.replace(from, to);
+    for entry in entries {
+        if entry.case == CaseMatch::Exact && !entry.whole_word {
+            result = result.replace(&entry.term, &entry.say);
+        } else {
+            result = entry.replace_all(&result, false);
+        }
     }
 
     result
 }
 
-fn apply_brand_pronunciation(text: &str, brands: &BTreeMap<String, String>) -> String {
+fn apply_brand_pronunciation(text: &str, brands: &[LexiconEntry], ssml: bool) -> String {
     let mut result = text.to_string();
     let mut entries: Vec<_> = brands.iter().collect();
-    entries.sort_by_key(|(key, _)| Reverse(key.len()));
+    entries.sort_by_key(|entry| Reverse(entry.term.len()));
 
-    for (from, to) in entries {
-        let pattern = Regex::new(&format!(r"(?i)\b{}\b", regex::escape(from))).unwrap();
-        result = pattern.replace_all(&result, to.as_str()).to_string();
+    for entry in entries {
+        result = ssml::map_unmarked(&result, |plain| entry.replace_all(plain, ssml));
     }
 
     result
@@ -1035,7 +1723,7 @@ fn apply_year_pronunciation(text: &str, mode: &YearMode, number_config: &NumberC
 }
 
 fn year_to_words(year: usize, number_config: &NumberConfig) -> String {
-    if year < 1000 || year > 2099 {
+    if !(1000..=2099).contains(&year) {
         return year.to_string();
     }
     let ones = [
@@ -1077,8 +1765,8 @@ fn year_to_words(year: usize, number_config: &NumberConfig) -> String {
             remainder_str.push_str(teens[remainder - 10]);
         } else {
             remainder_str.push_str(tens[remainder / 10]);
-            if remainder % 10 > 0 {
-                remainder_str.push_str(" ");
+            if !remainder.is_multiple_of(10) {
+                remainder_str.push(' ');
                 remainder_str.push_str(ones[remainder % 10]);
             }
         }
@@ -1126,11 +1814,11 @@ fn simple_number_to_words(n: usize) -> String {
 
     let mut parts: Vec<String> = Vec::new();
     if thousands > 0 {
-        parts.push(format!("{}", ones[thousands]));
+        parts.push(ones[thousands].to_string());
         parts.push("thousand".to_string());
     }
     if hundreds > 0 {
-        parts.push(format!("{}", ones[hundreds]));
+        parts.push(ones[hundreds].to_string());
         parts.push("hundred".to_string());
     }
 
@@ -1142,7 +1830,7 @@ fn simple_number_to_words(n: usize) -> String {
         } else {
             let ten = remainder / 10;
             parts.push(tens[ten].to_string());
-            if remainder % 10 > 0 {
+            if !remainder.is_multiple_of(10) {
                 parts.push(ones[remainder % 10].to_string());
             }
         }
@@ -1195,69 +1883,3 @@ fn apply_selector_pronunciation(text: &str, prefix: &str) -> String {
     })
     .to_string()
 }
-
-fn expand_acronyms(text: &str, cfg: &AbbreviationConfig) -> String {
-    if cfg.tokens.is_empty() {
-        return text.to_string();
-    }
-
-    let mut result = text.to_string();
-    for token in cfg.tokens.iter() {
-        let pattern = format!(
-            r"(?i)\b{}(?P<digits>\d+(?:\.\d+)*)?\b",
-            regex::escape(token)
-        );
-        let re = Regex::new(&pattern).unwrap();
-        result = re
-            .replace_all(&result, |caps: &regex::Captures| {
-                let letters = caps[0]
-                    .chars()
-                    .filter(|c| c.is_alphabetic())
-                    .map(|c| {
-                        let key = c.to_ascii_uppercase().to_string();
-                        cfg.letter_sounds
-                            .get(&key)
-                            .cloned()
-                            .unwrap_or_else(|| key.to_lowercase())
-                    })
-                    .collect::<Vec<_>>();
-                let mut spelled = letters.join(&cfg.letter_separator);
-                if let Some(digits) = caps.name("digits") {
-                    if !digits.as_str().trim().is_empty() {
-                        let number_spelled = digits
-                            .as_str()
-                            .split('.')
-                            .map(|group| spelled_digit_group(group, cfg))
-                            .filter(|grp| !grp.is_empty())
-                            .collect::<Vec<_>>()
-                            .join(&cfg.digit_separator);
-                        if !number_spelled.is_empty() {
-                            if !spelled.is_empty() {
-                                spelled.push(' ');
-                            }
-                            spelled.push_str(&number_spelled);
-                        }
-                    }
-                }
-                if spelled.is_empty() {
-                    caps[0].to_string()
-                } else {
-                    spelled
-                }
-            })
-            .to_string();
-    }
-    result
-}
-
-fn spelled_digit_group(group: &str, cfg: &AbbreviationConfig) -> String {
-    group
-        .chars()
-        .filter(|c| c.is_ascii_digit())
-        .map(|c| {
-            let key = c.to_string();
-            cfg.letter_sounds.get(&key).cloned().unwrap_or_else(|| key)
-        })
-        .collect::<Vec<_>>()
-        .join(&cfg.letter_separator)
-}
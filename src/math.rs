@@ -0,0 +1,512 @@
+//! Math in running text.
+//!
+//! Inline formulas such as "x^2 + y^2 = z^2", "a ≤ b", "√2" or "f(x) = 3x − 1" are read in
+//! words before the parenthesis, slash and hyphen rules take them apart, and the Unicode math
+//! symbols, superscript and subscript digits and Greek letters XTTS would skip are named. This
+//! runs before Unicode normalization, which would flatten "x²" into "x2". The word tables are
+//! shared with the LaTeX reader.
+
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::footnotes;
+
+/// An inline expression: operands joined by operators. Operands are numbers ("3", "3x"),
+/// single-letter variables, letter pairs with an exponent ("mc²"), Greek letters, function
+/// applications ("f(x)", "sin(t)") and parenthesized groups, optionally with a root sign, a
+/// `^` or superscript exponent and a subscript. Whether a match is really math is decided by [`is_math`].
+static RE_EXPRESSION: Lazy<Regex> = Lazy::new(|| {
+    let operand = r"(?:[√∛∜][ \t]*)?(?:\b(?:sin|cos|tan|log|ln|exp|sqrt|[a-zA-Z])\([^()\n]{1,40}\)|\([^()\n]{1,40}\)|\b\d+(?:\.\d+)?[a-zA-Z]?\b|\b[a-zA-Z]{2}(?:\^[a-zA-Z\d]|[⁰¹²³⁴⁵⁶⁷⁸⁹])|\b[a-zA-Z]\b|\b[α-ωΑ-Ω]\b)(?:\^(?:-?\d+|[a-zA-Z]|\([^()\n]{1,20}\))|[⁰¹²³⁴⁵⁶⁷⁸⁹⁺⁻ⁿ]+)?[₀₁₂₃₄₅₆₇₈₉]*";
+    let operator = r"[ \t]*(?:<=|>=|!=|==|[-+*/=<>×÷·⋅−±≤≥≠≈])[ \t]*";
+    Regex::new(&format!("{operand}(?:{operator}{operand})*")).unwrap()
+});
+/// A variable or known function applied to a parenthesized argument.
+static RE_APPLICATION: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b(?:sin|cos|tan|log|ln|exp|sqrt|[a-zA-Z])\(").unwrap());
+static RE_LETTERS: Lazy<Regex> = Lazy::new(|| Regex::new(r"[a-zA-Z]+").unwrap());
+/// An operator between two operands.
+static RE_OPERATOR: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"[\p{L}\p{N})⁰¹²³⁴⁵⁶⁷⁸⁹ⁿ₀₁₂₃₄₅₆₇₈₉][ \t]*(?:<=|>=|!=|==|[-+*/=<>×÷·⋅−±≤≥≠≈])[ \t]*[\p{L}\p{N}(√∛∜]").unwrap()
+});
+/// An operator with nothing on one side inside parentheses: "(2013+)", "(= 3)".
+static RE_DANGLING_OPERATOR: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[-+*/=<>][ \t]*\)|\([ \t]*[+*/=<>]").unwrap());
+/// `-` or `/` between spaces, which is arithmetic only when a variable is involved.
+static RE_SPACED_OPERATOR: Lazy<Regex> = Lazy::new(|| Regex::new(r"[ \t][-/][ \t]").unwrap());
+/// A root sign outside an expression and its argument.
+static RE_ROOT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"[√∛∜][ \t]*(?:\d+(?:\.\d+)?|[a-zA-Z]\b|\([^()\n]{1,40}\))?").unwrap()
+});
+/// A superscript exponent after a variable or unit of up to two letters ("mc²", "m²"), a
+/// number or a closing parenthesis. After a longer word it is a footnote reference and left
+/// to normalization, and so is one after a year or a number that ends a sentence (see
+/// `footnotes::is_number_reference`).
+static RE_POWER: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?P<base>\b[a-zA-Z]{1,2}|\d|\))(?P<exponent>[⁰¹²³⁴⁵⁶⁷⁸⁹⁺⁻ⁿ]+)").unwrap()
+});
+/// A subscript index after a lowercase variable ("x₁"); chemical formulas ("H₂O") keep theirs.
+static RE_INDEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?P<base>\b[a-z])(?P<index>[₀₁₂₃₄₅₆₇₈₉]+)").unwrap());
+
+/// Symbols read wherever they appear. The multiplication sign and the middle dot are only
+/// read inside an expression: "a 2×2 matrix" is left to `[pronunciation.replacements]`.
+const SPOKEN_ANYWHERE: &str = "≤≥≠≈≡∝≪≫±∓÷−∞∂∇∈∉⊂⊆∪∩∀∃∑∏∫";
+
+const GREEK: [(char, &str); 24] = [
+    ('α', "alpha"),
+    ('β', "beta"),
+    ('γ', "gamma"),
+    ('δ', "delta"),
+    ('ε', "epsilon"),
+    ('ζ', "zeta"),
+    ('η', "eta"),
+    ('θ', "theta"),
+    ('ι', "iota"),
+    ('κ', "kappa"),
+    ('λ', "lambda"),
+    ('μ', "mu"),
+    ('ν', "nu"),
+    ('ξ', "xi"),
+    ('ο', "omicron"),
+    ('π', "pi"),
+    ('ρ', "rho"),
+    ('σ', "sigma"),
+    ('τ', "tau"),
+    ('υ', "upsilon"),
+    ('φ', "phi"),
+    ('χ', "chi"),
+    ('ψ', "psi"),
+    ('ω', "omega"),
+];
+
+/// `[math]`: inline formulas and math symbols in running text.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub(crate) struct MathConfig {
+    /// Read inline expressions and math symbols in words.
+    enabled: bool,
+    /// Name Greek letters that stand alone ("π r²" is "pi r squared").
+    greek_letters: bool,
+}
+
+impl Default for MathConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            greek_letters: true,
+        }
+    }
+}
+
+impl MathConfig {
+    /// Whether stand-alone Greek letters are read by name.
+    pub(crate) fn names_greek_letters(&self) -> bool {
+        self.enabled && self.greek_letters
+    }
+}
+
+/// Read the math in `text` in words; returns the text and how many expressions and symbols
+/// were read. Fenced code blocks are left alone.
+pub(crate) fn verbalize(text: &str, cfg: &MathConfig) -> (String, usize) {
+    if !cfg.enabled {
+        return (text.to_string(), 0);
+    }
+    let mut count = 0;
+    let mut in_fence = false;
+    let lines: Vec<String> = text
+        .lines()
+        .map(|line| {
+            if line.trim_start().starts_with("```") {
+                in_fence = !in_fence;
+                return line.to_string();
+            }
+            if in_fence {
+                return line.to_string();
+            }
+            verbalize_line(line, cfg, &mut count)
+        })
+        .collect();
+    (lines.join("\n"), count)
+}
+
+fn verbalize_line(line: &str, cfg: &MathConfig, count: &mut usize) -> String {
+    let line = RE_EXPRESSION.replace_all(line, |caps: &Captures| {
+        let span = caps.get(0).unwrap();
+        if !is_math(span.as_str()) || in_code_or_address(line, span.start(), span.end()) {
+            return span.as_str().to_string();
+        }
+        *count += 1;
+        speak(span.as_str())
+    });
+    let line = RE_ROOT.replace_all(&line, |caps: &Captures| {
+        *count += 1;
+        speak(&caps[0])
+    });
+    let line = RE_POWER.replace_all(&line, |caps: &Captures| {
+        let exponent = caps.name("exponent").unwrap();
+        if caps["base"].starts_with(|ch: char| ch.is_ascii_digit())
+            && footnotes::is_number_reference(&line, exponent.start(), exponent.end())
+        {
+            return caps[0].to_string();
+        }
+        *count += 1;
+        format!(
+            "{} {}",
+            &caps["base"],
+            power_words(&speak(&script_to_ascii(&caps["exponent"])))
+        )
+    });
+    let line = RE_INDEX.replace_all(&line, |caps: &Captures| {
+        *count += 1;
+        format!("{} sub {}", &caps["base"], script_to_ascii(&caps["index"]))
+    });
+
+    let chars: Vec<char> = line.chars().collect();
+    let mut out = String::with_capacity(line.len());
+    for (idx, &ch) in chars.iter().enumerate() {
+        let before = idx.checked_sub(1).map(|idx| chars[idx]);
+        let after = chars.get(idx + 1).copied();
+        let word = if SPOKEN_ANYWHERE.contains(ch) {
+            symbol_word(ch)
+        } else if cfg.greek_letters
+            && !before.is_some_and(char::is_alphabetic)
+            && !after.is_some_and(char::is_alphabetic)
+        {
+            greek_name(ch)
+        } else {
+            None
+        };
+        let Some(word) = word else {
+            out.push(ch);
+            continue;
+        };
+        *count += 1;
+        if before.is_some_and(|before| !before.is_whitespace()) {
+            out.push(' ');
+        }
+        out.push_str(word);
+        if after.is_some_and(|after| !after.is_whitespace()) {
+            out.push(' ');
+        }
+    }
+    out
+}
+
+/// Whether an expression match is math rather than prose: a comparison, an exponent, a root
+/// or a function application, or arithmetic that cannot be a range ("1990 - 2000"), a
+/// fraction or date ("3/4"), a compound ("A/B testing") or a dimension ("2×2"). Words and
+/// code punctuation make it prose or code.
+fn is_math(span: &str) -> bool {
+    if !span.chars().all(|ch| {
+        ch.is_alphanumeric()
+            || ch.is_whitespace()
+            || "().,^".contains(ch)
+            || symbol_word(ch).is_some()
+            || is_superscript(ch)
+            || script_digit(ch).is_some()
+            || matches!(ch, '√' | '∛' | '∜')
+    }) {
+        return false;
+    }
+    // Words other than function names: "(clean + a bit of life)".
+    if RE_LETTERS
+        .find_iter(span)
+        .filter(|run| run.len() > 1)
+        .any(|run| {
+            function_word(run.as_str()).is_none()
+                && !(run.len() == 2
+                    && span[run.end()..].starts_with(|ch| ch == '^' || is_superscript(ch)))
+        })
+    {
+        return false;
+    }
+    if RE_DANGLING_OPERATOR.is_match(span) {
+        return false;
+    }
+    if span.contains(['^', '√', '∛', '∜']) || RE_APPLICATION.is_match(span) {
+        return true;
+    }
+    // A lone operand with a superscript ("x²", "cm²") is left to the exponent rule.
+    if !RE_OPERATOR.is_match(span) {
+        return false;
+    }
+    if span.contains([
+        '=', '<', '>', '≤', '≥', '≠', '≈', '+', '*', '÷', '⋅', '−', '±',
+    ]) || span.chars().any(is_superscript)
+    {
+        return true;
+    }
+    span.chars().any(char::is_alphabetic) && RE_SPACED_OPERATOR.is_match(span)
+}
+
+/// Whether the match at `start..end` sits in code or an address: glued to a word, a path or
+/// an option name ("top-k = 10", "s.len()"), followed by a block or statement ("p + p {"), or
+/// inside a URL or e-mail address.
+fn in_code_or_address(line: &str, start: usize, end: usize) -> bool {
+    let mut before = line[..start].chars().rev();
+    match before.next() {
+        Some(ch) if ch.is_alphanumeric() || "._/\\$@<#&".contains(ch) => return true,
+        Some('-') if before.next().is_some_and(char::is_alphanumeric) => return true,
+        _ => {}
+    }
+    let rest = &line[end..];
+    let mut after = rest.chars();
+    if let Some('.' | '_') = after.next()
+        && after.next().is_some_and(char::is_alphanumeric)
+    {
+        return true;
+    }
+    if rest.trim_start().starts_with(['{', ';']) {
+        return true;
+    }
+    let token_start = line[..start]
+        .rfind(char::is_whitespace)
+        .map_or(0, |idx| idx + 1);
+    let token_end = rest
+        .find(char::is_whitespace)
+        .map_or(line.len(), |idx| end + idx);
+    let token = &line[token_start..token_end];
+    token.contains("://") || token.contains("www.") || token.contains(['@', '?'])
+}
+
+/// Read a plain-text expression in words: "f(x) = 3x − 1" becomes "f of x equals 3 x minus
+/// 1".
+fn speak(expr: &str) -> String {
+    let mut words = String::new();
+    let mut rest = expr;
+    let mut previous = ' ';
+    while let Some(ch) = rest.chars().next() {
+        rest = &rest[ch.len_utf8()..];
+        match ch {
+            '^' => {
+                let (arg, used) = argument(rest);
+                rest = &rest[used..];
+                words.push_str(&format!(" {} ", power_words(&speak(arg))));
+            }
+            '√' | '∛' | '∜' => {
+                let (arg, used) = argument(rest);
+                rest = &rest[used..];
+                let index = match ch {
+                    '√' => "2",
+                    '∛' => "3",
+                    _ => "4",
+                };
+                words.push_str(&format!(" {} ", root_words(index, &speak(arg))));
+            }
+            ch if is_superscript(ch) => {
+                let len = rest
+                    .find(|c: char| !is_superscript(c))
+                    .unwrap_or(rest.len());
+                let exponent = script_to_ascii(&format!("{ch}{}", &rest[..len]));
+                rest = &rest[len..];
+                words.push_str(&format!(" {} ", power_words(&speak(&exponent))));
+            }
+            '₀'..='₉' => {
+                let len = rest
+                    .find(|c: char| !('₀'..='₉').contains(&c))
+                    .unwrap_or(rest.len());
+                let index = script_to_ascii(&format!("{ch}{}", &rest[..len]));
+                rest = &rest[len..];
+                words.push_str(&format!(" sub {index} "));
+            }
+            '<' | '>' | '!' | '=' if rest.starts_with('=') => {
+                rest = &rest[1..];
+                let symbol = match ch {
+                    '<' => '≤',
+                    '>' => '≥',
+                    '!' => '≠',
+                    _ => '=',
+                };
+                words.push_str(&format!(" {} ", symbol_word(symbol).unwrap_or_default()));
+            }
+            ch if ch.is_ascii_alphabetic() => {
+                let len = rest
+                    .find(|c: char| !c.is_ascii_alphabetic())
+                    .unwrap_or(rest.len());
+                let name = format!("{ch}{}", &rest[..len]);
+                rest = &rest[len..];
+                let applied = rest.starts_with('(');
+                if previous.is_alphanumeric() {
+                    words.push(' ');
+                }
+                match function_word(&name) {
+                    Some(word) if applied => words.push_str(&format!(" {word} of ")),
+                    Some(word) => words.push_str(&format!(" {word} ")),
+                    None if applied && name.len() == 1 => words.push_str(&format!(" {name} of ")),
+                    // Letters are variables: "xy" is "x y".
+                    None => {
+                        let letters: Vec<String> = name.chars().map(String::from).collect();
+                        words.push_str(&letters.join(" "));
+                    }
+                }
+                previous = name.chars().next_back().unwrap_or(ch);
+                continue;
+            }
+            ch if greek_name(ch).is_some() => {
+                words.push_str(&format!(" {} ", greek_name(ch).unwrap_or_default()));
+            }
+            ch if ch.is_ascii_digit() && previous.is_alphabetic() => {
+                words.push(' ');
+                words.push(ch);
+            }
+            ',' => words.push_str(", "),
+            '(' | ')' | '[' | ']' | '{' | '}' => words.push(' '),
+            ch => match symbol_word(ch) {
+                Some(word) => words.push_str(&format!(" {word} ")),
+                None => words.push(ch),
+            },
+        }
+        previous = ch;
+    }
+    words
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace(" ,", ",")
+}
+
+/// The argument of `^` or a root sign: a parenthesized group, a signed number or one letter.
+fn argument(text: &str) -> (&str, usize) {
+    let trimmed = text.trim_start();
+    let skipped = text.len() - trimmed.len();
+    if let Some(inner) = trimmed.strip_prefix('(') {
+        let len = inner.find(')').unwrap_or(inner.len());
+        let used = (len + 2).min(trimmed.len());
+        return (&inner[..len], skipped + used);
+    }
+    let sign = usize::from(trimmed.starts_with('-'));
+    let digits = trimmed[sign..]
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(trimmed.len() - sign);
+    let len = match digits {
+        0 => sign + trimmed[sign..].chars().next().map_or(0, char::len_utf8),
+        digits => sign + digits,
+    };
+    (&trimmed[..len], skipped + len)
+}
+
+/// The words for a math operator or relation symbol.
+pub(crate) fn symbol_word(symbol: char) -> Option<&'static str> {
+    Some(match symbol {
+        '+' => "plus",
+        '-' | '−' => "minus",
+        '*' | '×' | '·' | '⋅' => "times",
+        '/' => "over",
+        '÷' => "divided by",
+        '=' => "equals",
+        '<' => "is less than",
+        '>' => "is greater than",
+        '≤' => "is less than or equal to",
+        '≥' => "is greater than or equal to",
+        '≠' => "is not equal to",
+        '≈' => "is approximately",
+        '≡' => "is equivalent to",
+        '∼' => "is similar to",
+        '∝' => "is proportional to",
+        '≪' => "is much less than",
+        '≫' => "is much greater than",
+        '±' => "plus or minus",
+        '∓' => "minus or plus",
+        '∞' => "infinity",
+        '∂' => "partial",
+        '∇' => "nabla",
+        '∈' => "in",
+        '∉' => "not in",
+        '⊂' | '⊆' => "is a subset of",
+        '∪' => "union",
+        '∩' => "intersection",
+        '∀' => "for all",
+        '∃' => "there exists",
+        '→' => "to",
+        '⇒' => "implies",
+        '⇔' => "if and only if",
+        '∑' => "the sum of",
+        '∏' => "the product of",
+        '∫' => "the integral of",
+        '!' => "factorial",
+        '′' => "prime",
+        _ => return None,
+    })
+}
+
+/// The name of a Greek letter; capitals are read like their small letters.
+pub(crate) fn greek_name(letter: char) -> Option<&'static str> {
+    let small = letter.to_lowercase().next()?;
+    let small = if small == 'ς' { 'σ' } else { small };
+    GREEK
+        .iter()
+        .find(|(greek, _)| *greek == small)
+        .map(|(_, name)| *name)
+}
+
+/// Whether `name` is a Greek letter's name, as in LaTeX's `\alpha` or `\Omega`.
+pub(crate) fn is_greek_name(name: &str) -> bool {
+    let name = name.to_lowercase();
+    GREEK.iter().any(|(_, greek)| *greek == name)
+}
+
+/// The spoken name of a function ("sin" is "sine").
+pub(crate) fn function_word(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "sin" => "sine",
+        "cos" => "cosine",
+        "tan" => "tangent",
+        "log" => "log",
+        "ln" => "natural log",
+        "exp" => "exp",
+        "max" => "max",
+        "min" => "min",
+        "det" => "determinant",
+        "sqrt" => "the square root",
+        _ => return None,
+    })
+}
+
+/// "squared", "cubed" or "to the power of …" for a spoken exponent.
+pub(crate) fn power_words(exponent: &str) -> String {
+    match exponent {
+        "2" => "squared".to_string(),
+        "3" => "cubed".to_string(),
+        exponent => format!("to the power of {exponent}"),
+    }
+}
+
+/// "the square root of …" and friends for a spoken root index and radicand.
+pub(crate) fn root_words(index: &str, radicand: &str) -> String {
+    match index {
+        "" | "2" => format!("the square root of {radicand}"),
+        "3" => format!("the cube root of {radicand}"),
+        index => format!("the {index}th root of {radicand}"),
+    }
+}
+
+fn is_superscript(ch: char) -> bool {
+    matches!(ch, '⁰' | '¹' | '²' | '³' | '⁴'..='⁹' | '⁺' | '⁻' | 'ⁿ')
+}
+
+fn script_digit(ch: char) -> Option<char> {
+    match ch {
+        '⁰' => Some('0'),
+        '¹' => Some('1'),
+        '²' => Some('2'),
+        '³' => Some('3'),
+        '⁴'..='⁹' => char::from_u32(ch as u32 - '⁴' as u32 + '4' as u32),
+        '₀'..='₉' => char::from_u32(ch as u32 - '₀' as u32 + '0' as u32),
+        _ => None,
+    }
+}
+
+/// Superscript and subscript digits and signs as ASCII.
+fn script_to_ascii(script: &str) -> String {
+    script
+        .chars()
+        .map(|ch| match ch {
+            '⁺' => '+',
+            '⁻' => '-',
+            'ⁿ' => 'n',
+            ch => script_digit(ch).unwrap_or(ch),
+        })
+        .collect()
+}
@@ -0,0 +1,225 @@
+//! Page furniture removal.
+//!
+//! Text extracted from PDFs keeps what was printed around every page: running headers and
+//! footers ("A COMPREHENSIVE HISTORY OF CSS    37"), bare page numbers and the form feeds
+//! between pages. Left in place, unwrapping glues them into the middle of sentences, so they
+//! are removed from the whole document before chapters are split and lines are joined.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// A line holding nothing but a page number: `37`, `- 37 -`, `Page 37`, `37 of 412`, `xii`.
+/// Roman numerals must be lowercase; a capital "I" or "IV" is more likely a heading.
+static RE_PAGE_NUMBER: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^\s*(?i:page\s+)?[-–—]?\s*(?P<number>\d{1,4}|[ivxlc]{1,7})\s*[-–—]?(?:\s*(?:of|/)\s*\d{1,4})?\s*$",
+    )
+    .unwrap()
+});
+/// A well-formed lowercase roman numeral, so words such as "ill" or "civil" are not page
+/// numbers.
+static RE_ROMAN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(?:c{0,3})(?:xc|xl|l?x{0,3})(?:ix|iv|v?i{0,3})$").unwrap());
+static RE_DIGITS: Lazy<Regex> = Lazy::new(|| Regex::new(r"\d+").unwrap());
+
+/// Longest line (in chars) considered a running header or footer.
+const MAX_FURNITURE_CHARS: usize = 100;
+/// Without form feeds, repeats closer together than this many lines are not page furniture
+/// (a refrain, a repeated list entry).
+const MIN_PAGE_LINES: usize = 10;
+
+/// `[pages]`: running headers, footers and page numbers left by PDF extraction.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub(crate) struct PageConfig {
+    /// Remove lines repeating at page intervals (running headers and footers).
+    strip_running_heads: bool,
+    /// Remove lines holding only a page number.
+    strip_page_numbers: bool,
+    /// A header or footer must appear on at least this many pages.
+    min_repeats: usize,
+    /// Lines checked at the top and bottom of each page when pages are separated by form feeds.
+    edge_lines: usize,
+}
+
+impl Default for PageConfig {
+    fn default() -> Self {
+        Self {
+            strip_running_heads: true,
+            strip_page_numbers: true,
+            min_repeats: 3,
+            edge_lines: 2,
+        }
+    }
+}
+
+/// What was removed from the page layout.
+#[derive(Debug, Default)]
+pub(crate) struct PageStats {
+    pub(crate) running_heads: usize,
+    pub(crate) page_numbers: usize,
+    pub(crate) page_breaks: usize,
+}
+
+impl PageStats {
+    /// Fold the stats of another document into these.
+    pub(crate) fn absorb(&mut self, other: PageStats) {
+        self.running_heads += other.running_heads;
+        self.page_numbers += other.page_numbers;
+        self.page_breaks += other.page_breaks;
+    }
+}
+
+/// Remove form feeds, running headers and footers, and page numbers. Where a sentence runs
+/// on across a removed line, the blank lines around it are dropped so it is joined again.
+pub(crate) fn strip_page_furniture(text: &str, cfg: &PageConfig) -> (String, PageStats) {
+    let mut stats = PageStats {
+        page_breaks: text.trim_end().matches('\u{000C}').count(),
+        ..Default::default()
+    };
+    let pages: Vec<Vec<&str>> = text
+        .split('\u{000C}')
+        .map(|page| page.lines().collect())
+        .collect();
+    let paged = pages.len() > 1;
+    let lines: Vec<&str> = pages.iter().flatten().copied().collect();
+    // Only the first and last lines of a page can be furniture when the pages are known.
+    let at_edge: Vec<bool> = pages
+        .iter()
+        .flat_map(|page| {
+            let content: Vec<usize> = (0..page.len())
+                .filter(|&idx| !page[idx].trim().is_empty())
+                .collect();
+            (0..page.len()).map(move |idx| {
+                !paged
+                    || content.iter().take(cfg.edge_lines).any(|&edge| edge == idx)
+                    || content
+                        .iter()
+                        .rev()
+                        .take(cfg.edge_lines)
+                        .any(|&edge| edge == idx)
+            })
+        })
+        .collect();
+
+    let mut removed = vec![false; lines.len()];
+    if cfg.strip_page_numbers {
+        let numbers: Vec<usize> = (0..lines.len())
+            .filter(|&idx| at_edge[idx] && is_page_number(lines[idx]))
+            .collect();
+        if paged || is_page_sequence(&lines, &numbers, cfg.min_repeats) {
+            for idx in numbers {
+                removed[idx] = true;
+                stats.page_numbers += 1;
+            }
+        }
+    }
+    if cfg.strip_running_heads {
+        let mut repeats: HashMap<String, Vec<usize>> = HashMap::new();
+        for idx in (0..lines.len()).filter(|&idx| at_edge[idx] && !removed[idx]) {
+            if let Some(key) = furniture_key(lines[idx]) {
+                repeats.entry(key).or_default().push(idx);
+            }
+        }
+        for positions in repeats.values() {
+            let repeated = positions.len() >= cfg.min_repeats.max(2);
+            if repeated && (paged || is_regular(positions)) {
+                for &idx in positions {
+                    removed[idx] = true;
+                    stats.running_heads += 1;
+                }
+            }
+        }
+    }
+
+    if !paged && !removed.contains(&true) {
+        return (text.to_string(), stats);
+    }
+    (rejoin(&lines, &removed), stats)
+}
+
+/// Running heads differ from page to page only in their numbers.
+fn furniture_key(line: &str) -> Option<String> {
+    let line = line.trim();
+    if line.chars().count() > MAX_FURNITURE_CHARS || !line.chars().any(char::is_alphabetic) {
+        return None;
+    }
+    let key = RE_DIGITS.replace_all(&line.to_lowercase(), "#").to_string();
+    Some(key.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Whether repeats of a line sit a page apart: most gaps close to the typical gap, which
+/// must be longer than a few lines.
+fn is_regular(positions: &[usize]) -> bool {
+    let mut gaps: Vec<usize> = positions.windows(2).map(|pair| pair[1] - pair[0]).collect();
+    if gaps.is_empty() {
+        return false;
+    }
+    gaps.sort_unstable();
+    let median = gaps[gaps.len() / 2];
+    if median < MIN_PAGE_LINES {
+        return false;
+    }
+    let close = gaps
+        .iter()
+        .filter(|gap| gap.abs_diff(median) * 4 <= median)
+        .count();
+    close * 4 >= gaps.len() * 3
+}
+
+/// Whether `line` holds only a page number, in digits or as a valid roman numeral.
+fn is_page_number(line: &str) -> bool {
+    RE_PAGE_NUMBER.captures(line).is_some_and(|caps| {
+        let number = &caps["number"];
+        number.starts_with(|c: char| c.is_ascii_digit()) || RE_ROMAN.is_match(number)
+    })
+}
+
+/// Without form feeds, bare numbers only count as page numbers when they recur at page
+/// intervals and mostly count upwards.
+fn is_page_sequence(lines: &[&str], positions: &[usize], min_repeats: usize) -> bool {
+    let values: Vec<u32> = positions
+        .iter()
+        .filter_map(|&idx| RE_PAGE_NUMBER.captures(lines[idx])?["number"].parse().ok())
+        .collect();
+    if values.len() != positions.len() || values.len() < min_repeats.max(2) {
+        return false;
+    }
+    let rising = values.windows(2).filter(|pair| pair[1] > pair[0]).count();
+    rising * 5 >= (values.len() - 1) * 4 && is_regular(positions)
+}
+
+/// Drop removed lines; blank lines around them go too when the text before them stops
+/// mid-sentence.
+fn rejoin(lines: &[&str], removed: &[bool]) -> String {
+    let mut out: Vec<&str> = Vec::with_capacity(lines.len());
+    let mut pending_blanks = 0;
+    let mut across_removal = false;
+    for (line, &removed) in lines.iter().zip(removed) {
+        if removed {
+            across_removal = true;
+            continue;
+        }
+        if line.trim().is_empty() {
+            pending_blanks += 1;
+            continue;
+        }
+        let runs_on = across_removal && out.last().is_some_and(|prev| !ends_sentence(prev));
+        if !runs_on && !out.is_empty() {
+            out.extend(std::iter::repeat_n("", pending_blanks));
+        }
+        out.push(line);
+        pending_blanks = 0;
+        across_removal = false;
+    }
+    out.join("\n")
+}
+
+fn ends_sentence(line: &str) -> bool {
+    line.trim_end()
+        .trim_end_matches(['"', '\'', ')', '”', '’'])
+        .ends_with(['.', '!', '?', ':'])
+}
@@ -0,0 +1,373 @@
+//! Pause model.
+//!
+//! Structural events the cleaner recognizes (paragraph ends, headings, list items, dash
+//! asides, parentheticals, scene breaks) are realized through one `[pauses]` policy instead
+//! of each stage inventing its own punctuation. Realizations that later stages would
+//! rewrite or that depend on the output format (`ellipsis`, `break`, `token`) are inserted
+//! as per-event marker characters and expanded once cleaning is done.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::ssml;
+
+/// A Markdown ATX heading (`# Title`, `## Title ##`).
+static RE_HEADING: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s{0,3}#{1,6}[ \t]+(?P<title>.*?)(?:[ \t]+#+)?[ \t]*$").unwrap());
+
+/// First private-use character of the per-event markers.
+const MARKER_BASE: u32 = 0xE010;
+
+/// A structural event that calls for a pause.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PauseEvent {
+    ParagraphEnd,
+    Heading,
+    ListItem,
+    DashAside,
+    Parenthetical,
+    SceneBreak,
+}
+
+impl PauseEvent {
+    const ALL: [PauseEvent; 6] = [
+        PauseEvent::ParagraphEnd,
+        PauseEvent::Heading,
+        PauseEvent::ListItem,
+        PauseEvent::DashAside,
+        PauseEvent::Parenthetical,
+        PauseEvent::SceneBreak,
+    ];
+
+    fn marker(self) -> char {
+        let idx = Self::ALL
+            .iter()
+            .position(|event| *event == self)
+            .unwrap_or(0);
+        char::from_u32(MARKER_BASE + idx as u32).unwrap_or('\u{E010}')
+    }
+
+    fn from_marker(ch: char) -> Option<Self> {
+        let idx = (ch as u32).checked_sub(MARKER_BASE)? as usize;
+        Self::ALL.get(idx).copied()
+    }
+}
+
+/// How a pause is realized in the output.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Pause {
+    /// No pause; the event is reduced to a space.
+    None,
+    /// A full stop.
+    Period,
+    /// A comma.
+    #[default]
+    Comma,
+    /// Three periods.
+    Ellipsis,
+    /// A paragraph break.
+    Newline,
+    /// An SSML `<break>` using the event's `break_times` entry (a period outside SSML).
+    Break,
+    /// The engine-specific `pauses.token`.
+    Token,
+}
+
+/// `[pauses]`: how each structural event is paced.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub(crate) struct PauseConfig {
+    /// Pause closing a paragraph that does not already end in punctuation.
+    paragraph_end: Pause,
+    /// Pause after a Markdown `#` heading, which becomes its own paragraph. In SSML output
+    /// `period` and `comma` become a `<break>` timed by `break_times.heading`.
+    heading: Pause,
+    /// Pause between consecutive list items. In SSML output `period` and `comma` become a
+    /// `<break>` timed by `break_times.list_item`.
+    list_item: Pause,
+    /// Pause replacing an em or en dash when `unicode.dash_mode = "pause"`.
+    dash_aside: Pause,
+    /// Pause replacing each parenthesis of an aside.
+    parenthetical: Pause,
+    /// Pause standing in for a scene break (`structure.scene_break_markers`).
+    scene_break: Pause,
+    /// Text emitted for `token` pauses (for engines with a dedicated pause token).
+    token: String,
+    /// `<break>` times used by `break` pauses in SSML output.
+    break_times: BreakTimes,
+}
+
+impl Default for PauseConfig {
+    fn default() -> Self {
+        Self {
+            paragraph_end: Pause::Period,
+            heading: Pause::Period,
+            list_item: Pause::Comma,
+            dash_aside: Pause::Comma,
+            parenthetical: Pause::Comma,
+            scene_break: Pause::Ellipsis,
+            token: String::new(),
+            break_times: BreakTimes::default(),
+        }
+    }
+}
+
+/// `[pauses.break_times]`: SSML `<break>` time per event.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+struct BreakTimes {
+    /// Break closing a paragraph.
+    paragraph_end: String,
+    /// Break after a heading.
+    heading: String,
+    /// Break between list items.
+    list_item: String,
+    /// Break for a dash aside.
+    dash_aside: String,
+    /// Break around a parenthetical.
+    parenthetical: String,
+    /// Break for a scene break.
+    scene_break: String,
+}
+
+impl Default for BreakTimes {
+    fn default() -> Self {
+        Self {
+            paragraph_end: "500ms".to_string(),
+            heading: "750ms".to_string(),
+            list_item: "300ms".to_string(),
+            dash_aside: "200ms".to_string(),
+            parenthetical: "200ms".to_string(),
+            scene_break: "1500ms".to_string(),
+        }
+    }
+}
+
+impl PauseConfig {
+    fn pause(&self, event: PauseEvent) -> Pause {
+        match event {
+            PauseEvent::ParagraphEnd => self.paragraph_end,
+            PauseEvent::Heading => self.heading,
+            PauseEvent::ListItem => self.list_item,
+            PauseEvent::DashAside => self.dash_aside,
+            PauseEvent::Parenthetical => self.parenthetical,
+            PauseEvent::SceneBreak => self.scene_break,
+        }
+    }
+
+    /// The pause for `event` in this output: SSML marks headings and list items with a
+    /// timed `<break>` even where plain text would use punctuation.
+    fn resolve(&self, event: PauseEvent, ssml: bool) -> Pause {
+        match self.pause(event) {
+            Pause::Period | Pause::Comma
+                if ssml && matches!(event, PauseEvent::Heading | PauseEvent::ListItem) =>
+            {
+                Pause::Break
+            }
+            pause => pause,
+        }
+    }
+
+    fn break_time(&self, event: PauseEvent) -> &str {
+        let times = &self.break_times;
+        match event {
+            PauseEvent::ParagraphEnd => &times.paragraph_end,
+            PauseEvent::Heading => &times.heading,
+            PauseEvent::ListItem => &times.list_item,
+            PauseEvent::DashAside => &times.dash_aside,
+            PauseEvent::Parenthetical => &times.parenthetical,
+            PauseEvent::SceneBreak => &times.scene_break,
+        }
+    }
+
+    /// Text standing in for `event` in the middle of a line.
+    pub(crate) fn inline(&self, event: PauseEvent) -> String {
+        match self.pause(event) {
+            Pause::None => " ".to_string(),
+            Pause::Period => ". ".to_string(),
+            Pause::Comma => ", ".to_string(),
+            Pause::Newline => "\n\n".to_string(),
+            Pause::Ellipsis | Pause::Break | Pause::Token => format!(" {} ", event.marker()),
+        }
+    }
+
+    /// Close `line` with the pause for `event`, unless it already ends in one. A `newline`
+    /// pause leaves a trailing newline so the next line starts a new paragraph.
+    pub(crate) fn close(&self, line: &str, event: PauseEvent, ssml: bool) -> String {
+        let line = line.trim_end();
+        if line.is_empty() || ends_with_pause(line) {
+            return line.to_string();
+        }
+        match self.resolve(event, ssml) {
+            Pause::None => line.to_string(),
+            Pause::Newline => format!("{line}\n"),
+            Pause::Period => format!("{line}."),
+            Pause::Comma => format!("{line},"),
+            Pause::Ellipsis => format!("{line}{}", event.marker()),
+            Pause::Break | Pause::Token => format!("{line} {}", event.marker()),
+        }
+    }
+
+    /// Expand a marker character left by an `ellipsis`, `break` or `token` pause. With `ssml`, breaks
+    /// become `<break>` elements and tokens are escaped.
+    pub(crate) fn expand_marker(&self, ch: char, ssml: bool) -> Option<String> {
+        let event = PauseEvent::from_marker(ch)?;
+        Some(match self.resolve(event, ssml) {
+            Pause::Break if ssml => {
+                let time = self.break_time(event);
+                if time.is_empty() {
+                    String::new()
+                } else {
+                    format!("<break time=\"{}\"/>", ssml::escape(time))
+                }
+            }
+            Pause::Token if ssml => ssml::escape(&self.token),
+            Pause::Token => self.token.clone(),
+            Pause::Ellipsis => "...".to_string(),
+            _ => ".".to_string(),
+        })
+    }
+
+    /// Expand every pause marker in plain-text output.
+    pub(crate) fn expand_markers(&self, text: &str) -> String {
+        text.chars()
+            .map(|ch| {
+                self.expand_marker(ch, false)
+                    .unwrap_or_else(|| ch.to_string())
+            })
+            .collect()
+    }
+}
+
+/// Whether `line` already ends in terminal punctuation (possibly inside closing quotes or
+/// brackets) or a pause marker.
+fn ends_with_pause(line: &str) -> bool {
+    let trimmed = line.trim_end_matches(['"', '\'', ')', ']', '»', '”', '’']);
+    trimmed
+        .chars()
+        .next_back()
+        .is_some_and(|ch| ".!?…:;,".contains(ch) || PauseEvent::from_marker(ch).is_some())
+}
+
+/// Give Markdown headings and scene breaks their own paragraph, realized through the
+/// `heading` and `scene_break` pauses. Scene breaks are only recognized when
+/// `scene_break_markers` is non-empty; each one found is counted in `scene_breaks`.
+pub(crate) fn mark_headings_and_scene_breaks(
+    text: &str,
+    cfg: &PauseConfig,
+    ssml: bool,
+    scene_break_markers: &[String],
+    scene_breaks: &mut usize,
+) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut separate_next = false;
+    for line in text.lines() {
+        let structural = if is_scene_break(line, scene_break_markers) {
+            *scene_breaks += 1;
+            Some(scene_break_line(cfg))
+        } else {
+            RE_HEADING.captures(line).map(|caps| {
+                cfg.close(&caps["title"], PauseEvent::Heading, ssml)
+                    .trim_end()
+                    .to_string()
+            })
+        };
+        match structural {
+            Some(paragraph) => {
+                if out.last().is_some_and(|last| !last.trim().is_empty()) {
+                    out.push(String::new());
+                }
+                if !paragraph.is_empty() {
+                    out.push(paragraph);
+                }
+                separate_next = true;
+            }
+            None => {
+                if separate_next && !line.trim().is_empty() {
+                    out.push(String::new());
+                }
+                separate_next = false;
+                out.push(line.to_string());
+            }
+        }
+    }
+    out.join("\n")
+}
+
+/// A line made of one repeated character (spaces aside) that repeats a marker's character at
+/// least as often as the marker does: with `***` and `#`, both `* * * *` and `#` qualify.
+fn is_scene_break(line: &str, markers: &[String]) -> bool {
+    let mut chars = line.chars().filter(|c| !c.is_whitespace());
+    let Some(first) = chars.next() else {
+        return false;
+    };
+    let mut count = 1;
+    for ch in chars {
+        if ch != first {
+            return false;
+        }
+        count += 1;
+    }
+    markers.iter().any(|marker| {
+        let marker: Vec<char> = marker.chars().filter(|c| !c.is_whitespace()).collect();
+        !marker.is_empty() && marker.iter().all(|&c| c == first) && count >= marker.len()
+    })
+}
+
+/// A scene break stands alone, so its pause is the whole paragraph.
+fn scene_break_line(cfg: &PauseConfig) -> String {
+    match cfg.pause(PauseEvent::SceneBreak) {
+        Pause::None | Pause::Newline => String::new(),
+        Pause::Period => ".".to_string(),
+        Pause::Comma => ",".to_string(),
+        Pause::Ellipsis | Pause::Break | Pause::Token => {
+            PauseEvent::SceneBreak.marker().to_string()
+        }
+    }
+}
+
+/// Replace each parenthesis with the `parenthetical` pause.
+pub(crate) fn realize_parentheticals(text: &str, cfg: &PauseConfig) -> String {
+    let pause = cfg.inline(PauseEvent::Parenthetical);
+    text.replace(['(', ')'], &pause)
+}
+
+/// Close every paragraph with the `paragraph_end` pause; `newline` separates paragraphs
+/// with a blank line.
+pub(crate) fn close_paragraphs(text: &str, cfg: &PauseConfig) -> String {
+    if cfg.pause(PauseEvent::ParagraphEnd) == Pause::Newline {
+        return text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(str::trim_end)
+            .collect::<Vec<_>>()
+            .join("\n\n");
+    }
+    text.lines()
+        .map(|line| cfg.close(line, PauseEvent::ParagraphEnd, false))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Config, OutputFormat, clean_text};
+
+    #[test]
+    fn ssml_breaks_after_headings_and_between_list_items() {
+        let mut config = Config::default();
+        config.io.output_format = OutputFormat::Ssml;
+        let (ssml, _) = clean_text("# Heading One\n\n- first item\n- second item\n", &config);
+        assert!(
+            ssml.contains("Heading One <break time=\"750ms\"/>"),
+            "{ssml}"
+        );
+        assert!(
+            ssml.contains("first item <break time=\"300ms\"/> second item"),
+            "{ssml}"
+        );
+    }
+}
@@ -0,0 +1,174 @@
+//! SSML rendering.
+//!
+//! When `io.output_format = "ssml"`, stages that know something about a span wrap it in
+//! private-use marker characters instead of losing that knowledge; [`render`] turns the
+//! markers into SSML elements once cleaning is done and escapes everything else.
+
+use crate::SsmlConfig;
+use crate::pauses::PauseConfig;
+
+/// Opens a span that should be read as characters (`<say-as interpret-as="characters">`).
+pub(crate) const SAY_AS_OPEN: char = '\u{E000}';
+/// Closes a [`SAY_AS_OPEN`] span.
+pub(crate) const SAY_AS_CLOSE: char = '\u{E001}';
+/// Opens a substitution: `SUB_OPEN original SUB_ALIAS alias SUB_CLOSE`.
+pub(crate) const SUB_OPEN: char = '\u{E002}';
+/// Separates the original text of a substitution from its alias.
+pub(crate) const SUB_ALIAS: char = '\u{E003}';
+/// Closes a [`SUB_OPEN`] span.
+pub(crate) const SUB_CLOSE: char = '\u{E004}';
+
+/// Wrap `text` so it is read character by character.
+pub(crate) fn say_as_characters(text: &str) -> String {
+    format!("{SAY_AS_OPEN}{text}{SAY_AS_CLOSE}")
+}
+
+/// Wrap `original` so it is read as `alias`.
+pub(crate) fn substitute(original: &str, alias: &str) -> String {
+    format!("{SUB_OPEN}{original}{SUB_ALIAS}{alias}{SUB_CLOSE}")
+}
+
+/// Apply `f` to the parts of `text` outside marked spans, so later stages leave markup alone.
+pub(crate) fn map_unmarked(text: &str, mut f: impl FnMut(&str) -> String) -> String {
+    if !text.contains([SAY_AS_OPEN, SUB_OPEN]) {
+        return f(text);
+    }
+    let mut result = String::with_capacity(text.len());
+    let mut plain = String::new();
+    let mut marked = String::new();
+    let mut depth = 0usize;
+    for ch in text.chars() {
+        match ch {
+            SAY_AS_OPEN | SUB_OPEN => {
+                if depth == 0 {
+                    result.push_str(&f(&plain));
+                    plain.clear();
+                }
+                depth += 1;
+                marked.push(ch);
+            }
+            SAY_AS_CLOSE | SUB_CLOSE if depth > 0 => {
+                depth -= 1;
+                marked.push(ch);
+                if depth == 0 {
+                    result.push_str(&marked);
+                    marked.clear();
+                }
+            }
+            _ if depth > 0 => marked.push(ch),
+            _ => plain.push(ch),
+        }
+    }
+    result.push_str(&marked);
+    result.push_str(&f(&plain));
+    result
+}
+
+/// Render cleaned, marker-annotated text as an SSML document.
+///
+/// Every non-empty line is a paragraph, sentences are split on `.`, `!` or `?` followed by
+/// whitespace, and pause markers become `<break>` elements or engine tokens.
+pub(crate) fn render(text: &str, cfg: &SsmlConfig, pauses: &PauseConfig) -> String {
+    let mut out = String::new();
+    if cfg.lang.is_empty() {
+        out.push_str("<speak version=\"1.0\" xmlns=\"http://www.w3.org/2001/10/synthesis\">\n");
+    } else {
+        out.push_str(&format!(
+            "<speak version=\"1.0\" xmlns=\"http://www.w3.org/2001/10/synthesis\" xml:lang=\"{}\">\n",
+            escape(&cfg.lang)
+        ));
+    }
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        out.push_str("<p>");
+        for sentence in split_sentences(line) {
+            out.push_str("<s>");
+            out.push_str(&render_inline(&sentence, pauses));
+            out.push_str("</s>");
+        }
+        out.push_str("</p>\n");
+    }
+    out.push_str("</speak>\n");
+    out
+}
+
+/// Split on sentence-final punctuation outside marked spans.
+fn split_sentences(paragraph: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    let mut chars = paragraph.chars().peekable();
+    while let Some(ch) = chars.next() {
+        current.push(ch);
+        match ch {
+            SAY_AS_OPEN | SUB_OPEN => depth += 1,
+            SAY_AS_CLOSE | SUB_CLOSE => depth = depth.saturating_sub(1),
+            '.' | '!' | '?'
+                if depth == 0 && chars.peek().is_some_and(|next| next.is_whitespace()) =>
+            {
+                sentences.push(current.trim().to_string());
+                current.clear();
+            }
+            _ => {}
+        }
+    }
+    if !current.trim().is_empty() {
+        sentences.push(current.trim().to_string());
+    }
+    sentences
+}
+
+fn render_inline(sentence: &str, pauses: &PauseConfig) -> String {
+    let mut out = String::with_capacity(sentence.len());
+    let mut original = String::new();
+    let mut alias = String::new();
+    let mut in_sub = false;
+    let mut in_alias = false;
+    for ch in sentence.chars() {
+        match ch {
+            SAY_AS_OPEN => out.push_str("<say-as interpret-as=\"characters\">"),
+            SAY_AS_CLOSE => out.push_str("</say-as>"),
+            SUB_OPEN => {
+                in_sub = true;
+                in_alias = false;
+                original.clear();
+                alias.clear();
+            }
+            SUB_ALIAS => in_alias = true,
+            SUB_CLOSE => {
+                out.push_str(&format!(
+                    "<sub alias=\"{}\">{}</sub>",
+                    escape(alias.trim()),
+                    escape(&original)
+                ));
+                in_sub = false;
+                in_alias = false;
+            }
+            _ if in_sub && in_alias => alias.push(ch),
+            _ if in_sub => original.push(ch),
+            _ => match pauses.expand_marker(ch, true) {
+                Some(pause) => out.push_str(&pause),
+                None => out.push_str(&escape_char(ch)),
+            },
+        }
+    }
+    out
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    text.chars().map(escape_char).collect()
+}
+
+fn escape_char(ch: char) -> String {
+    match ch {
+        '&' => "&amp;".to_string(),
+        '<' => "&lt;".to_string(),
+        '>' => "&gt;".to_string(),
+        '"' => "&quot;".to_string(),
+        '\'' => "&apos;".to_string(),
+        _ => ch.to_string(),
+    }
+}
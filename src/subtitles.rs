@@ -0,0 +1,317 @@
+//! Subtitle input and output (SRT and WebVTT).
+//!
+//! Cues are parsed with their timing, their markup, speaker labels and sound cues are removed,
+//! and their text goes through the cleaning pipeline as one running text, so sentences that
+//! continue from one cue into the next are read as such. A private-use marker between cues
+//! lets the cleaned text be split back into the cues it came from, and the result is written
+//! in the input's format with the original timestamps, or as plain narration.
+
+use anyhow::{Result, bail};
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{CleanStats, Config, OutputFormat, clean_text};
+
+/// Separates the text of two cues while they are cleaned together.
+const CUE_BREAK: char = '\u{E030}';
+
+static RE_BLANK_LINE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\n[ \t]*\n").unwrap());
+/// `start --> end [settings]`.
+static RE_TIMING: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*(?P<start>[\d:.,]+)\s+-->\s+(?P<end>[\d:.,]+)(?P<settings>.*)$").unwrap()
+});
+/// HTML-like cue markup (`<i>`, `<font color=..>`, `<v Speaker>`, `<00:01.500>`) and SSA
+/// override blocks (`{\an8}`).
+static RE_MARKUP: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>\n]*>|\{\\[^}\n]*\}").unwrap());
+/// A speaker label at the start of a line: `JOHN:`, `DR. SMITH:`, `[Anna]:`, `>> `.
+static RE_SPEAKER: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?:>>+\s*)?(?:(?:\[[^\]\n]+\]|\p{Lu}[\p{Lu}\p{N} .'-]*)\s*:\s+)?").unwrap()
+});
+/// A dialogue dash opening a line.
+static RE_DIALOGUE_DASH: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[-‐–—]\s+").unwrap());
+/// Sound cues: anything bracketed, parentheticals naming a sound (`(LAUGHS)`,
+/// `(door slams)`), and music notes with the lyrics between them.
+static RE_SOUND_CUE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?x)
+        \[[^\]\n]*\]
+        | (?i:\([^()\n]*\b(?:
+            laugh(?:s|ing|ter)? | chuckl(?:es?|ing) | giggl(?:es?|ing) | sigh(?:s|ing)?
+            | gasp(?:s|ing)? | cough(?:s|ing)? | sniff(?:s|les|ling)? | groan(?:s|ing)?
+            | scream(?:s|ing)? | cr(?:y|ies|ying) | sob(?:s|bing)? | applause | cheer(?:s|ing)
+            | clap(?:s|ping)? | music | sings? | singing | humm?(?:s|ing) | whisper(?:s|ing)
+            | inaudible | indistinct | crosstalk | silence | knock(?:s|ing)? | footsteps
+            | slams? | rings? | ringing | beep(?:s|ing)? | thud | bang(?:s|ing)? | explosions?
+            | static | gunshots? | sound | noise | chatter(?:ing)?
+        )\b[^()\n]*\))
+        | [♪♫][^♪♫\n]*[♪♫] | [♪♫]
+        ",
+    )
+    .unwrap()
+});
+/// A line that is only a parenthetical, as in `(laughs)`.
+static RE_PAREN_LINE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\([^)\n]*\)$").unwrap());
+static RE_SENTENCE_END: Lazy<Regex> = Lazy::new(|| Regex::new(r#"[.!?…]["'”’)\]]*$"#).unwrap());
+
+/// `[subtitles]`: SRT and WebVTT captions (`io.input_format = "subtitles"`).
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub(crate) struct SubtitleConfig {
+    /// What is written: the cleaned cues with their timing, or plain narration text.
+    output: SubtitleOutput,
+    /// Remove speaker labels (`JOHN:`, `[Anna]:`, `>>`, `<v Anna>`) and dialogue dashes.
+    strip_speakers: bool,
+    /// Remove sound cues: bracketed text (`[MUSIC]`), stand-alone parentheticals,
+    /// parentheticals naming a sound (`(LAUGHS)`) and music notes with their lyrics.
+    strip_sound_cues: bool,
+    /// Merge consecutive cues until one ends a sentence; a merged cue runs from the first
+    /// cue's start to the last cue's end.
+    merge_sentences: bool,
+}
+
+impl Default for SubtitleConfig {
+    fn default() -> Self {
+        Self {
+            output: SubtitleOutput::Cues,
+            strip_speakers: true,
+            strip_sound_cues: true,
+            merge_sentences: false,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+enum SubtitleOutput {
+    /// Write the cues back in the input's format with their original timestamps.
+    #[default]
+    Cues,
+    /// Write the cue text as narration; merged cues become paragraphs.
+    Text,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SubtitleFormat {
+    Srt,
+    WebVtt,
+}
+
+/// One caption: its timing as written and its text.
+#[derive(Debug)]
+struct Cue {
+    identifier: Option<String>,
+    start: String,
+    end: String,
+    settings: String,
+    text: String,
+}
+
+/// Whether `text` looks like a WebVTT file or an SRT file (a cue number, then a timing line).
+pub(crate) fn looks_like_subtitles(text: &str) -> bool {
+    let mut lines = text
+        .trim_start_matches('\u{feff}')
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| line.is_empty());
+    match (lines.next(), lines.next()) {
+        (Some(first), _) if first.starts_with("WEBVTT") => true,
+        (Some(first), Some(second)) => {
+            first.chars().all(|ch| ch.is_ascii_digit()) && RE_TIMING.is_match(second)
+        }
+        _ => false,
+    }
+}
+
+/// Clean a subtitle file; returns the file to write and the pipeline stats.
+pub(crate) fn clean(raw: &str, config: &Config) -> Result<(String, CleanStats)> {
+    let cfg = &config.subtitles;
+    let (format, cues) = parse(raw);
+    if cues.is_empty() {
+        bail!("no subtitle cues found");
+    }
+    let parsed = cues.len();
+    let mut cues: Vec<Cue> = cues
+        .into_iter()
+        .filter_map(|mut cue| {
+            cue.text = strip_cue(&cue.text, cfg);
+            (!cue.text.is_empty()).then_some(cue)
+        })
+        .collect();
+    let emptied = parsed - cues.len();
+    if cfg.merge_sentences {
+        cues = merge_sentences(cues);
+    }
+    info!(
+        "Parsed {parsed} subtitle cues, dropped {emptied} without speech, kept {}",
+        cues.len()
+    );
+
+    if cfg.output == SubtitleOutput::Text {
+        let separator = if cfg.merge_sentences { "\n\n" } else { "\n" };
+        let text = cues
+            .iter()
+            .map(|cue| cue.text.as_str())
+            .collect::<Vec<_>>()
+            .join(separator);
+        return Ok(clean_text(&text, config));
+    }
+    if matches!(config.io.output_format, OutputFormat::Ssml) {
+        bail!("subtitle cues are written as text; use subtitles.output = \"text\" for SSML");
+    }
+
+    let joined = cues
+        .iter()
+        .map(|cue| cue.text.as_str())
+        .collect::<Vec<_>>()
+        .join(&format!(" {CUE_BREAK} "));
+    let (cleaned, mut stats) = clean_text(&joined, config);
+    let mut texts: Vec<String> = cleaned
+        .split(CUE_BREAK)
+        .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect();
+    if texts.len() != cues.len() {
+        // A stage swallowed a boundary; fall back to cleaning each cue on its own.
+        warn!("Cue boundaries were lost while cleaning; cleaning each cue separately");
+        stats = CleanStats::default();
+        texts = cues
+            .iter()
+            .map(|cue| {
+                let (text, cue_stats) = clean_text(&cue.text, config);
+                stats.absorb(cue_stats);
+                text.split_whitespace().collect::<Vec<_>>().join(" ")
+            })
+            .collect();
+    }
+    for (cue, text) in cues.iter_mut().zip(texts) {
+        cue.text = text;
+    }
+    cues.retain(|cue| !cue.text.is_empty());
+    let written = render(format, &cues);
+    stats.output_length = written.len();
+    stats.paragraph_count = cues.len();
+    Ok((written, stats))
+}
+
+/// Parse the cues of an SRT or WebVTT file. Blocks without a timing line (the WebVTT header,
+/// `NOTE`, `STYLE` and `REGION` blocks) are skipped.
+fn parse(raw: &str) -> (SubtitleFormat, Vec<Cue>) {
+    let text = raw
+        .trim_start_matches('\u{feff}')
+        .replace("\r\n", "\n")
+        .replace('\r', "\n");
+    let format = if text.trim_start().starts_with("WEBVTT") {
+        SubtitleFormat::WebVtt
+    } else {
+        SubtitleFormat::Srt
+    };
+    let mut cues = Vec::new();
+    for block in RE_BLANK_LINE.split(&text) {
+        let lines: Vec<&str> = block
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .collect();
+        let Some(timing) = lines.iter().position(|line| RE_TIMING.is_match(line)) else {
+            continue;
+        };
+        let Some(caps) = RE_TIMING.captures(lines[timing]) else {
+            continue;
+        };
+        cues.push(Cue {
+            identifier: (timing > 0).then(|| lines[timing - 1].trim().to_string()),
+            start: caps["start"].to_string(),
+            end: caps["end"].to_string(),
+            settings: caps["settings"].trim().to_string(),
+            text: lines[timing + 1..].join("\n"),
+        });
+    }
+    (format, cues)
+}
+
+/// Remove markup, speaker labels and sound cues from a cue's text, joining its lines. A line
+/// whose dialogue dash or speaker label was removed starts a new sentence, so two speakers
+/// are not read as one.
+fn strip_cue(text: &str, cfg: &SubtitleConfig) -> String {
+    let text = RE_MARKUP.replace_all(text, "");
+    let text = html_escape::decode_html_entities(&text);
+    let mut joined = String::new();
+    for line in text.lines() {
+        let mut line = line.trim().to_string();
+        let mut new_turn = false;
+        if cfg.strip_speakers {
+            let stripped = RE_DIALOGUE_DASH.replace(&line, "");
+            let stripped = RE_SPEAKER.replace(&stripped, "").to_string();
+            new_turn = stripped.len() != line.len();
+            line = stripped;
+        }
+        if cfg.strip_sound_cues {
+            if RE_PAREN_LINE.is_match(&line) {
+                line.clear();
+            }
+            line = RE_SOUND_CUE.replace_all(&line, "").to_string();
+        }
+        let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        // What is left of a cue line that was only a label or a sound cue.
+        if !line.chars().any(char::is_alphanumeric) {
+            continue;
+        }
+        if !joined.is_empty() {
+            if new_turn && !RE_SENTENCE_END.is_match(&joined) {
+                joined.push('.');
+            }
+            joined.push(' ');
+        }
+        joined.push_str(&line);
+    }
+    joined
+}
+
+/// Merge runs of cues that continue a sentence into one cue.
+fn merge_sentences(cues: Vec<Cue>) -> Vec<Cue> {
+    let mut merged: Vec<Cue> = Vec::with_capacity(cues.len());
+    let mut open = false;
+    for cue in cues {
+        let ends_sentence = RE_SENTENCE_END.is_match(&cue.text);
+        match merged.last_mut() {
+            Some(last) if open => {
+                last.end = cue.end;
+                last.text.push(' ');
+                last.text.push_str(&cue.text);
+            }
+            _ => merged.push(cue),
+        }
+        open = !ends_sentence;
+    }
+    merged
+}
+
+/// Write cues in `format`: SRT cues are renumbered, WebVTT cues keep their identifiers and
+/// settings.
+fn render(format: SubtitleFormat, cues: &[Cue]) -> String {
+    let mut out = String::new();
+    if format == SubtitleFormat::WebVtt {
+        out.push_str("WEBVTT\n\n");
+    }
+    for (idx, cue) in cues.iter().enumerate() {
+        match format {
+            SubtitleFormat::Srt => out.push_str(&format!("{}\n", idx + 1)),
+            SubtitleFormat::WebVtt => {
+                if let Some(identifier) = &cue.identifier {
+                    out.push_str(identifier);
+                    out.push('\n');
+                }
+            }
+        }
+        out.push_str(&format!("{} --> {}", cue.start, cue.end));
+        if format == SubtitleFormat::WebVtt && !cue.settings.is_empty() {
+            out.push(' ');
+            out.push_str(&cue.settings);
+        }
+        out.push('\n');
+        out.push_str(&cue.text);
+        out.push_str("\n\n");
+    }
+    out
+}
@@ -0,0 +1,520 @@
+//! Symbols and emoji.
+//!
+//! Typographic symbols, arrows, separators and emoji are either flattened by NFKC ("™"
+//! becomes "TM") or read differently by every voice, so they are replaced before
+//! normalization from a built-in table that `[symbols.words]` can override. Each class has a
+//! policy (speak, drop, pause or keep), and context rules keep names such as "C#" and "C++",
+//! headings, bullets, code and URLs out of the way.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::pauses::{PauseConfig, PauseEvent};
+
+/// One emoji: a flag, a keycap, or a pictograph with its variation selector, skin tone and
+/// zero-width-joined parts.
+static RE_EMOJI: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"[\x{1F1E6}-\x{1F1FF}]{2}|[0-9#*]\x{FE0F}?\x{20E3}|\p{Extended_Pictographic}[\x{FE0F}\x{1F3FB}-\x{1F3FF}]*(?:\x{200D}\p{Extended_Pictographic}[\x{FE0F}\x{1F3FB}-\x{1F3FF}]*)*",
+    )
+    .unwrap()
+});
+static RE_TOKEN: Lazy<Regex> = Lazy::new(|| Regex::new(r"\S+").unwrap());
+/// An HTML entity left in the text (`&amp;`, `&#8212;`).
+static RE_ENTITY: Lazy<Regex> = Lazy::new(|| Regex::new(r"^&(?:[a-zA-Z]+|#\d+);").unwrap());
+/// A `#` color: three to eight hex digits with at least one letter.
+static RE_HEX_COLOR: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^#(?:[0-9a-fA-F]{3,4}|[0-9a-fA-F]{6}|[0-9a-fA-F]{8})\b").unwrap());
+
+static TABLE: Lazy<HashMap<&'static str, (SymbolClass, &'static str)>> = Lazy::new(|| {
+    SYMBOLS
+        .iter()
+        .map(|(symbol, class, words)| (*symbol, (*class, *words)))
+        .chain(
+            EMOJI
+                .iter()
+                .map(|(emoji, name)| (*emoji, (SymbolClass::Emoji, *name))),
+        )
+        .collect()
+});
+
+/// The built-in symbol table; an empty reading drops the symbol even when spoken.
+const SYMBOLS: [(&str, SymbolClass, &str); 38] = [
+    ("&", SymbolClass::Typography, "and"),
+    ("@", SymbolClass::Typography, "at"),
+    ("#", SymbolClass::Typography, "hash"),
+    ("~", SymbolClass::Typography, "about"),
+    ("§", SymbolClass::Typography, "section"),
+    ("¶", SymbolClass::Typography, "paragraph"),
+    ("©", SymbolClass::Typography, "copyright"),
+    ("℗", SymbolClass::Typography, "sound recording copyright"),
+    ("®", SymbolClass::Typography, ""),
+    ("™", SymbolClass::Typography, ""),
+    ("℠", SymbolClass::Typography, ""),
+    ("°", SymbolClass::Typography, "degrees"),
+    ("℃", SymbolClass::Typography, "degrees Celsius"),
+    ("℉", SymbolClass::Typography, "degrees Fahrenheit"),
+    ("†", SymbolClass::Typography, "dagger"),
+    ("‡", SymbolClass::Typography, "double dagger"),
+    ("‰", SymbolClass::Typography, "per mille"),
+    ("№", SymbolClass::Typography, "number"),
+    ("+", SymbolClass::Operator, "plus"),
+    ("=", SymbolClass::Operator, "equals"),
+    ("→", SymbolClass::Arrow, "to"),
+    ("⟶", SymbolClass::Arrow, "to"),
+    ("➔", SymbolClass::Arrow, "to"),
+    ("➜", SymbolClass::Arrow, "to"),
+    ("←", SymbolClass::Arrow, "from"),
+    ("↑", SymbolClass::Arrow, "up"),
+    ("↓", SymbolClass::Arrow, "down"),
+    ("↗", SymbolClass::Arrow, "up"),
+    ("↘", SymbolClass::Arrow, "down"),
+    ("↔", SymbolClass::Arrow, "and"),
+    ("⇒", SymbolClass::Arrow, "implies"),
+    ("⇐", SymbolClass::Arrow, "is implied by"),
+    ("⇔", SymbolClass::Arrow, "if and only if"),
+    ("|", SymbolClass::Separator, "bar"),
+    ("•", SymbolClass::Separator, "bullet"),
+    ("·", SymbolClass::Separator, "dot"),
+    ("‣", SymbolClass::Separator, "bullet"),
+    ("◦", SymbolClass::Separator, "bullet"),
+];
+
+/// Common emoji by CLDR short name, without variation selectors.
+const EMOJI: [(&str, &str); 119] = [
+    ("😀", "grinning face"),
+    ("😃", "grinning face with big eyes"),
+    ("😄", "grinning face with smiling eyes"),
+    ("😁", "beaming face with smiling eyes"),
+    ("😆", "grinning squinting face"),
+    ("😅", "grinning face with sweat"),
+    ("🤣", "rolling on the floor laughing"),
+    ("😂", "face with tears of joy"),
+    ("🙂", "slightly smiling face"),
+    ("🙃", "upside-down face"),
+    ("😉", "winking face"),
+    ("😊", "smiling face with smiling eyes"),
+    ("😇", "smiling face with halo"),
+    ("🥰", "smiling face with hearts"),
+    ("😍", "smiling face with heart-eyes"),
+    ("😘", "face blowing a kiss"),
+    ("😋", "face savoring food"),
+    ("😜", "winking face with tongue"),
+    ("🤪", "zany face"),
+    ("🤗", "smiling face with open hands"),
+    ("🤔", "thinking face"),
+    ("🤨", "face with raised eyebrow"),
+    ("😐", "neutral face"),
+    ("😑", "expressionless face"),
+    ("😶", "face without mouth"),
+    ("🙄", "face with rolling eyes"),
+    ("😏", "smirking face"),
+    ("😬", "grimacing face"),
+    ("😌", "relieved face"),
+    ("😔", "pensive face"),
+    ("😴", "sleeping face"),
+    ("😷", "face with medical mask"),
+    ("🤯", "exploding head"),
+    ("😎", "smiling face with sunglasses"),
+    ("🤓", "nerd face"),
+    ("😕", "confused face"),
+    ("😟", "worried face"),
+    ("😮", "face with open mouth"),
+    ("😲", "astonished face"),
+    ("😳", "flushed face"),
+    ("🥺", "pleading face"),
+    ("😢", "crying face"),
+    ("😭", "loudly crying face"),
+    ("😱", "face screaming in fear"),
+    ("😤", "face with steam from nose"),
+    ("😡", "enraged face"),
+    ("😠", "angry face"),
+    ("🤬", "face with symbols on mouth"),
+    ("💀", "skull"),
+    ("💩", "pile of poo"),
+    ("🤖", "robot"),
+    ("🙈", "see-no-evil monkey"),
+    ("👋", "waving hand"),
+    ("👌", "OK hand"),
+    ("✌", "victory hand"),
+    ("🤞", "crossed fingers"),
+    ("👈", "backhand index pointing left"),
+    ("👉", "backhand index pointing right"),
+    ("👆", "backhand index pointing up"),
+    ("👇", "backhand index pointing down"),
+    ("☝", "index pointing up"),
+    ("👍", "thumbs up"),
+    ("👎", "thumbs down"),
+    ("👏", "clapping hands"),
+    ("🙌", "raising hands"),
+    ("🙏", "folded hands"),
+    ("💪", "flexed biceps"),
+    ("👀", "eyes"),
+    ("🧠", "brain"),
+    ("🤷", "person shrugging"),
+    ("🤦", "person facepalming"),
+    ("🧑‍💻", "technologist"),
+    ("👨‍💻", "man technologist"),
+    ("👩‍💻", "woman technologist"),
+    ("❤", "red heart"),
+    ("💔", "broken heart"),
+    ("💯", "hundred points"),
+    ("💥", "collision"),
+    ("💡", "light bulb"),
+    ("🔥", "fire"),
+    ("✨", "sparkles"),
+    ("⭐", "star"),
+    ("🌟", "glowing star"),
+    ("⚡", "high voltage"),
+    ("🎉", "party popper"),
+    ("🎊", "confetti ball"),
+    ("🎯", "bullseye"),
+    ("🏆", "trophy"),
+    ("🚀", "rocket"),
+    ("📌", "pushpin"),
+    ("📎", "paperclip"),
+    ("📝", "memo"),
+    ("📚", "books"),
+    ("📖", "open book"),
+    ("📈", "chart increasing"),
+    ("📉", "chart decreasing"),
+    ("📊", "bar chart"),
+    ("🔍", "magnifying glass tilted left"),
+    ("🔑", "key"),
+    ("🔒", "locked"),
+    ("🔗", "link"),
+    ("🛠", "hammer and wrench"),
+    ("⚙", "gear"),
+    ("💻", "laptop"),
+    ("📱", "mobile phone"),
+    ("⏰", "alarm clock"),
+    ("⏳", "hourglass not done"),
+    ("✅", "check mark button"),
+    ("✔", "check mark"),
+    ("☑", "check box with check"),
+    ("❌", "cross mark"),
+    ("❗", "red exclamation mark"),
+    ("❓", "red question mark"),
+    ("⚠", "warning"),
+    ("🚫", "prohibited"),
+    ("🛑", "stop sign"),
+    ("💬", "speech balloon"),
+    ("🌍", "globe showing Europe-Africa"),
+    ("☕", "hot beverage"),
+];
+
+/// `[symbols]`: typographic symbols, arrows, separators and emoji.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub(crate) struct SymbolConfig {
+    /// Typographic symbols: `&`, `@`, `#`, `~`, `§`, `©`, `™`, `°`.
+    typography: SymbolPolicy,
+    /// `+` and `=` outside formulas.
+    operators: SymbolPolicy,
+    /// Arrows: `→`, `↑`, `↔`, `⇒`.
+    arrows: SymbolPolicy,
+    /// Separators inside a line: `|`, `•`, `·`.
+    separators: SymbolPolicy,
+    /// Emoji, spoken by their CLDR short name; emoji missing from the table are dropped.
+    emoji: SymbolPolicy,
+    /// Words for symbols and emoji, overriding the built-in table; `""` drops the symbol.
+    words: BTreeMap<String, String>,
+    /// Names written with symbols, read as a whole before any symbol rule ("C#", "C++").
+    names: BTreeMap<String, String>,
+}
+
+impl Default for SymbolConfig {
+    fn default() -> Self {
+        Self {
+            typography: SymbolPolicy::Speak,
+            operators: SymbolPolicy::Speak,
+            arrows: SymbolPolicy::Speak,
+            separators: SymbolPolicy::Pause,
+            emoji: SymbolPolicy::Drop,
+            words: BTreeMap::new(),
+            names: [
+                ("C#", "C sharp"),
+                ("F#", "F sharp"),
+                ("C++", "C plus plus"),
+                ("G++", "G plus plus"),
+                ("Notepad++", "Notepad plus plus"),
+            ]
+            .into_iter()
+            .map(|(name, words)| (name.to_string(), words.to_string()))
+            .collect(),
+        }
+    }
+}
+
+impl SymbolConfig {
+    fn policy(&self, class: SymbolClass) -> SymbolPolicy {
+        match class {
+            SymbolClass::Typography => self.typography,
+            SymbolClass::Operator => self.operators,
+            SymbolClass::Arrow => self.arrows,
+            SymbolClass::Separator => self.separators,
+            SymbolClass::Emoji => self.emoji,
+        }
+    }
+}
+
+/// What happens to the symbols of one class.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+enum SymbolPolicy {
+    /// Read the symbol's words.
+    #[default]
+    Speak,
+    /// Remove the symbol.
+    Drop,
+    /// Replace the symbol with the `pauses.dash_aside` pause.
+    Pause,
+    /// Leave the symbol for later stages.
+    Keep,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SymbolClass {
+    Typography,
+    Operator,
+    Arrow,
+    Separator,
+    Emoji,
+}
+
+/// Replace symbols and emoji in `text`; returns the text and how many were replaced. Fenced
+/// code blocks are left alone.
+pub(crate) fn verbalize(text: &str, cfg: &SymbolConfig, pauses: &PauseConfig) -> (String, usize) {
+    let mut count = 0;
+    let mut names: Vec<(&String, &String)> = cfg.names.iter().collect();
+    names.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+    let mut in_fence = false;
+    let lines: Vec<String> = text
+        .lines()
+        .map(|line| {
+            if line.trim_start().starts_with("```") {
+                in_fence = !in_fence;
+                return line.to_string();
+            }
+            if in_fence {
+                return line.to_string();
+            }
+            let mut line = line.to_string();
+            for (name, words) in &names {
+                line = replace_name(&line, name, words, &mut count);
+            }
+            verbalize_line(&line, cfg, pauses, &mut count)
+        })
+        .collect();
+    (lines.join("\n"), count)
+}
+
+/// Replace `name` where it stands as a word of its own.
+fn replace_name(line: &str, name: &str, words: &str, count: &mut usize) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut last = 0;
+    for (idx, _) in line.match_indices(name) {
+        let end = idx + name.len();
+        let before = line[..idx].chars().next_back();
+        let after = line[end..].chars().next();
+        if idx < last
+            || before.is_some_and(|ch| ch.is_alphanumeric() || ch == '_')
+            || after.is_some_and(|ch| ch.is_alphanumeric() || "_#+".contains(ch))
+        {
+            continue;
+        }
+        out.push_str(&line[last..idx]);
+        out.push_str(words);
+        last = end;
+        *count += 1;
+    }
+    out.push_str(&line[last..]);
+    out
+}
+
+fn verbalize_line(
+    line: &str,
+    cfg: &SymbolConfig,
+    pauses: &PauseConfig,
+    count: &mut usize,
+) -> String {
+    // Headings, rules, scene breaks and table borders keep their symbols, emoji aside.
+    let structural = !line.chars().any(char::is_alphanumeric);
+    let addresses: Vec<(usize, usize)> = RE_TOKEN
+        .find_iter(line)
+        .filter(|token| token.as_str().contains("://") || token.as_str().contains("www."))
+        .map(|token| (token.start(), token.end()))
+        .collect();
+    let emoji: HashMap<usize, usize> = RE_EMOJI
+        .find_iter(line)
+        .map(|found| (found.start(), found.end()))
+        .collect();
+
+    let mut out = String::with_capacity(line.len());
+    let mut idx = 0;
+    while let Some(ch) = line[idx..].chars().next() {
+        let mut end = idx + ch.len_utf8();
+        let reading = if addresses
+            .iter()
+            .any(|&(start, stop)| (start..stop).contains(&idx))
+        {
+            None
+        } else if let Some(&stop) = emoji.get(&idx).filter(|_| !is_symbol(ch)) {
+            end = stop;
+            Some(emoji_reading(&line[idx..stop], cfg))
+        } else if structural {
+            None
+        } else {
+            symbol_reading(line, idx, ch, cfg).map(|(class, words, extra)| {
+                end += extra;
+                (class, words)
+            })
+        };
+        let Some((class, words)) = reading else {
+            out.push(ch);
+            idx += ch.len_utf8();
+            continue;
+        };
+        let replacement = match cfg.policy(class) {
+            SymbolPolicy::Keep => {
+                out.push_str(&line[idx..end]);
+                idx = end;
+                continue;
+            }
+            SymbolPolicy::Speak => words,
+            SymbolPolicy::Drop => String::new(),
+            SymbolPolicy::Pause => pauses.inline(PauseEvent::DashAside),
+        };
+        *count += 1;
+        let before = out.chars().next_back();
+        let after = line[end..].chars().next();
+        let replacement = replacement.trim();
+        if replacement.is_empty() {
+            // "R&D" without the symbol is still two words.
+            if before.is_some_and(char::is_alphanumeric) && after.is_some_and(char::is_alphanumeric)
+            {
+                out.push(' ');
+            }
+        } else {
+            if before.is_some_and(|before| !before.is_whitespace()) {
+                out.push(' ');
+            }
+            out.push_str(replacement);
+            if after.is_some_and(|after| !after.is_whitespace()) {
+                out.push(' ');
+            }
+        }
+        idx = end;
+    }
+    out
+}
+
+/// Whether `ch` is in the table as a symbol rather than an emoji ("©", "↔").
+fn is_symbol(ch: char) -> bool {
+    TABLE
+        .get(ch.to_string().as_str())
+        .is_some_and(|(class, _)| *class != SymbolClass::Emoji)
+}
+
+/// The words for an emoji: its own entry, or that of its first part with skin tones and
+/// variation selectors removed; keycaps are read as their key.
+fn emoji_reading(emoji: &str, cfg: &SymbolConfig) -> (SymbolClass, String) {
+    let bare: String = emoji
+        .chars()
+        .filter(|ch| !matches!(ch, '\u{FE0F}' | '\u{1F3FB}'..='\u{1F3FF}'))
+        .collect();
+    if let Some(key) = bare.strip_suffix('\u{20E3}') {
+        return (SymbolClass::Emoji, key.to_string());
+    }
+    let first = bare.split('\u{200D}').next().unwrap_or_default();
+    let words = [emoji, bare.as_str(), first]
+        .iter()
+        .find_map(|key| lookup(key, cfg))
+        .map(|(_, words)| words)
+        .unwrap_or_default();
+    (SymbolClass::Emoji, words)
+}
+
+fn lookup(symbol: &str, cfg: &SymbolConfig) -> Option<(SymbolClass, String)> {
+    let builtin = TABLE.get(symbol);
+    match cfg.words.get(symbol) {
+        Some(words) => Some((
+            builtin.map_or(SymbolClass::Typography, |(class, _)| *class),
+            words.clone(),
+        )),
+        None => builtin.map(|(class, words)| (*class, words.to_string())),
+    }
+}
+
+/// How the symbol `ch` at `idx` is read in its context, and how many bytes after it belong to
+/// the reading; `None` leaves it alone.
+fn symbol_reading(
+    line: &str,
+    idx: usize,
+    ch: char,
+    cfg: &SymbolConfig,
+) -> Option<(SymbolClass, String, usize)> {
+    let (class, words) = lookup(&ch.to_string(), cfg)?;
+    let before = line[..idx].chars().next_back();
+    let rest = &line[idx + ch.len_utf8()..];
+    let after = rest.chars().next();
+    let spaced = |side: Option<char>| side.is_none_or(char::is_whitespace);
+    let word = |side: Option<char>| side.is_some_and(char::is_alphanumeric);
+    match ch {
+        // "R&D", "Q & A"; "&args" and "&&" are code, "&amp;" an entity.
+        '&' if RE_ENTITY.is_match(&line[idx..]) => None,
+        '&' if (spaced(before) && spaced(after)) || (word(before) && word(after)) => {
+            Some((class, words, 0))
+        }
+        '&' => None,
+        // "#1" is a number and "#5B83AD" a color; "# Title", "##" and "page#2" are left.
+        '#' if word(before) || !word(after) => None,
+        '#' if RE_HEX_COLOR.is_match(&line[idx..])
+            && rest
+                .chars()
+                .take_while(char::is_ascii_hexdigit)
+                .any(|c| c.is_ascii_alphabetic()) =>
+        {
+            Some((class, "hex".to_string(), 0))
+        }
+        '#' if after.is_some_and(|c| c.is_ascii_digit()) => Some((class, "number".to_string(), 0)),
+        // "~5" is about five; "~/path" is a path.
+        '~' if rest.trim_start().starts_with(|c: char| c.is_ascii_digit()) => {
+            Some((class, words, 0))
+        }
+        '~' => None,
+        // "a + b", "Firefox2+Safari3" and "Chrome 49+"; a bullet ("+ item") and code ("\d+",
+        // "i++", "+=") keep theirs.
+        '+' if before.is_some() && spaced(before) && spaced(after) => Some((class, words, 0)),
+        '+' if word(before) && word(after) => Some((class, words, 0)),
+        '+' if before.is_some_and(|c| c.is_ascii_digit())
+            && !matches!(after, Some('+' | '='))
+            && !word(after) =>
+        {
+            Some((class, words, 0))
+        }
+        '+' => None,
+        // "==", "=>", "<=", `class="x"` are code or markup.
+        '=' if before.is_some_and(|c| "=<>!:+-*/".contains(c))
+            || after.is_some_and(|c| "=>\"'".contains(c)) =>
+        {
+            None
+        }
+        // "25°C" is "25 degrees Celsius".
+        '°' => match after {
+            Some(unit @ ('C' | 'F')) if !word(rest[1..].chars().next()) => {
+                let unit = if unit == 'C' { "Celsius" } else { "Fahrenheit" };
+                Some((class, format!("{words} {unit}"), 1))
+            }
+            _ => Some((class, words, 0)),
+        },
+        // Separators between spaces ("Home | Blog"); bullets opening a line are list markers
+        // and "a|b" or "||" is code.
+        '|' | '•' | '·' | '‣' | '◦' if line[..idx].trim().is_empty() => None,
+        '|' | '•' | '·' | '‣' | '◦' if !(spaced(before) && spaced(after)) => None,
+        _ => Some((class, words, 0)),
+    }
+}
@@ -0,0 +1,252 @@
+//! Chat transcripts.
+//!
+//! A conversation copied out of a chat UI carries the page around it ("Skip to content",
+//! "Chat history", the disclaimer under the input box), marks each turn with a line such as
+//! "You said:" or "ChatGPT said:", has no hard wraps (every line is a paragraph or a heading)
+//! and shows lists as indented blocks without bullet markers. This mode removes the chrome,
+//! turns the markers into spoken attributions or separate speaker segments, and rewrites the
+//! indented blocks as bullets so `[lists]` reads them as enumerations.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::chapters::Chapter;
+
+/// A turn marker on its own line: "You said:", "ChatGPT said:".
+static RE_TURN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(?P<speaker>\p{L}[\p{L}\p{N} ._-]{0,39}?) said:\s*$").unwrap());
+
+/// `[transcript]`: conversations pasted from a chat UI (`io.input_format = "transcript"`).
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub(crate) struct TranscriptConfig {
+    /// What happens to the "Speaker said:" lines that open each turn.
+    turns: TurnMode,
+    /// Paragraph read before a turn in `attribute` mode; `{speaker}` is filled in.
+    attribution: String,
+    /// Spoken names for speakers, by the name in the marker (`You = "The user"`).
+    speakers: BTreeMap<String, String>,
+    /// UI lines removed wherever they appear (whole line, case-insensitive).
+    chrome_lines: Vec<String>,
+    /// UI lines removed when they start with one of these.
+    chrome_prefixes: Vec<String>,
+    /// Read each line as its own paragraph; pasted chats have no hard wraps.
+    lines_are_paragraphs: bool,
+    /// Rewrite runs of indented blocks without bullet markers as a bulleted list.
+    indented_lists: bool,
+}
+
+impl Default for TranscriptConfig {
+    fn default() -> Self {
+        Self {
+            turns: TurnMode::Attribute,
+            attribution: "{speaker} said:".to_string(),
+            speakers: BTreeMap::new(),
+            chrome_lines: [
+                "Skip to content",
+                "Chat history",
+                "Copy code",
+                "Copy",
+                "Edit",
+                "Share",
+                "Open sidebar",
+                "New chat",
+                "Regenerate",
+            ]
+            .map(str::to_string)
+            .to_vec(),
+            chrome_prefixes: ["ChatGPT can make mistakes", "Thought for "]
+                .map(str::to_string)
+                .to_vec(),
+            lines_are_paragraphs: true,
+            indented_lists: true,
+        }
+    }
+}
+
+impl TranscriptConfig {
+    /// Whether every turn is written as its own segment file.
+    pub(crate) fn segments(&self) -> bool {
+        self.turns == TurnMode::Segments
+    }
+
+    fn is_chrome(&self, line: &str) -> bool {
+        let line = line.trim();
+        self.chrome_lines
+            .iter()
+            .any(|chrome| chrome.eq_ignore_ascii_case(line))
+            || self
+                .chrome_prefixes
+                .iter()
+                .any(|prefix| line.starts_with(prefix.as_str()))
+    }
+
+    fn speaker<'a>(&'a self, name: &'a str) -> &'a str {
+        self.speakers.get(name).map_or(name, String::as_str)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+enum TurnMode {
+    /// Replace each marker with the `attribution` paragraph.
+    #[default]
+    Attribute,
+    /// Write each turn as its own segment, named after its speaker in the manifest.
+    Segments,
+    /// Remove the markers.
+    Drop,
+}
+
+/// Whether `text` looks like a pasted chat: turn markers from at least two speakers.
+pub(crate) fn looks_like_transcript(text: &str) -> bool {
+    let mut speakers: Vec<&str> = Vec::new();
+    for caps in text
+        .lines()
+        .filter_map(|line| RE_TURN.captures(line.trim()))
+    {
+        let speaker = caps.name("speaker").map_or("", |speaker| speaker.as_str());
+        if !speakers.contains(&speaker) {
+            speakers.push(speaker);
+        }
+        if speakers.len() >= 2 {
+            return true;
+        }
+    }
+    false
+}
+
+/// Split a transcript into documents: one per turn in `segments` mode, otherwise one with
+/// spoken attributions or none. `bullet` is the marker given to indented list items.
+pub(crate) fn to_documents(text: &str, cfg: &TranscriptConfig, bullet: &str) -> Vec<Chapter> {
+    let mut turns: Vec<(Option<String>, Vec<&str>)> = vec![(None, Vec::new())];
+    for line in text.lines() {
+        if cfg.is_chrome(line) {
+            continue;
+        }
+        match RE_TURN.captures(line.trim()) {
+            Some(caps) => turns.push((Some(caps["speaker"].to_string()), Vec::new())),
+            None => {
+                if let Some((_, lines)) = turns.last_mut() {
+                    lines.push(line);
+                }
+            }
+        }
+    }
+    let turns: Vec<(Option<String>, String)> = turns
+        .into_iter()
+        .map(|(speaker, lines)| (speaker, layout(&lines, cfg, bullet)))
+        .filter(|(speaker, text)| speaker.is_some() || !text.trim().is_empty())
+        .collect();
+
+    if cfg.turns == TurnMode::Segments {
+        return turns
+            .into_iter()
+            .filter(|(_, text)| !text.trim().is_empty())
+            .map(|(speaker, text)| Chapter {
+                title: Some(speaker.map_or_else(
+                    || "Transcript".to_string(),
+                    |speaker| cfg.speaker(&speaker).to_string(),
+                )),
+                text,
+            })
+            .collect();
+    }
+    let mut out = String::new();
+    for (speaker, text) in turns {
+        if let Some(speaker) = speaker
+            && cfg.turns == TurnMode::Attribute
+        {
+            out.push_str(&cfg.attribution.replace("{speaker}", cfg.speaker(&speaker)));
+            out.push_str("\n\n");
+        }
+        out.push_str(&text);
+        out.push_str("\n\n");
+    }
+    vec![Chapter {
+        title: None,
+        text: out.trim_end().to_string(),
+    }]
+}
+
+/// Lay out the lines of one turn: lines become paragraphs, and runs of indented blocks
+/// become consecutive bullet items (an indented block's lines form one item).
+fn layout(lines: &[&str], cfg: &TranscriptConfig, bullet: &str) -> String {
+    let mut blocks: Vec<String> = Vec::new();
+    let mut items: Vec<String> = Vec::new();
+    let mut item: Vec<&str> = Vec::new();
+    let flush_item = |item: &mut Vec<&str>, items: &mut Vec<String>| {
+        if !item.is_empty() {
+            items.push(format!("{bullet}{}", item.join(" ")));
+            item.clear();
+        }
+    };
+    let mut plain: Vec<&str> = Vec::new();
+    for &line in lines {
+        let indented = line.starts_with([' ', '\t']);
+        if line.trim().is_empty() {
+            flush_item(&mut item, &mut items);
+            if !plain.is_empty() {
+                blocks.push(plain.join("\n"));
+                plain.clear();
+            }
+            continue;
+        }
+        if cfg.indented_lists && indented {
+            if !plain.is_empty() {
+                blocks.push(plain.join("\n"));
+                plain.clear();
+            }
+            item.push(line.trim());
+            continue;
+        }
+        flush_item(&mut item, &mut items);
+        if !items.is_empty() {
+            blocks.push(items.join("\n"));
+            items.clear();
+        }
+        plain.push(line);
+    }
+    flush_item(&mut item, &mut items);
+    if !items.is_empty() {
+        blocks.push(items.join("\n"));
+    }
+    if !plain.is_empty() {
+        blocks.push(plain.join("\n"));
+    }
+    if cfg.lines_are_paragraphs {
+        blocks = blocks
+            .into_iter()
+            .map(|block| {
+                if block.starts_with(bullet) || !block.contains('\n') {
+                    block
+                } else {
+                    split_lines(&block, bullet)
+                }
+            })
+            .collect();
+    }
+    blocks.join("\n\n")
+}
+
+/// Put a blank line between the lines of a block, keeping runs of bullet lines together.
+fn split_lines(block: &str, bullet: &str) -> String {
+    let mut out = String::new();
+    let mut previous_bullet = false;
+    for line in block.lines() {
+        let is_bullet = line.trim_start().starts_with(bullet);
+        if !out.is_empty() {
+            out.push_str(if is_bullet && previous_bullet {
+                "\n"
+            } else {
+                "\n\n"
+            });
+        }
+        out.push_str(line);
+        previous_bullet = is_bullet;
+    }
+    out
+}
@@ -0,0 +1,255 @@
+//! Hard-wrap repair.
+//!
+//! Text extracted from PDFs and typeset books breaks words at line ends ("inter-" /
+//! "national") and scatters soft hyphens through words; dehyphenation rejoins split words
+//! before lines are unwrapped, keeping the hyphen of genuine compounds ("well-known").
+//! Before unwrapping, line breaks that look intentional (poetry, addresses, unmarked lists)
+//! become paragraph breaks so only soft wraps are joined.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashSet;
+
+use crate::{ParagraphBoundary, StructureConfig, WrapDetection};
+
+/// Soft hyphen: an invisible break opportunity that should never reach the engine.
+const SOFT_HYPHEN: char = '\u{00AD}';
+/// A soft hyphen at a line end means the word continues on the next line.
+static RE_SOFT_HYPHEN_BREAK: Lazy<Regex> = Lazy::new(|| Regex::new(r"\u{00AD}\n[ \t]*").unwrap());
+
+static RE_WORD: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b[\p{L}]+(?:-[\p{L}]+)*\b").unwrap());
+/// The word fragment before a line-end hyphen.
+static RE_TRAILING_FRAGMENT: Lazy<Regex> = Lazy::new(|| Regex::new(r"([\p{L}\d]+)-$").unwrap());
+/// The word fragment that continues on the next line.
+static RE_LEADING_FRAGMENT: Lazy<Regex> = Lazy::new(|| Regex::new(r"^([\p{L}\d]+)").unwrap());
+
+/// First elements that are almost always written with a hyphen.
+const COMPOUND_PREFIXES: &[&str] = &[
+    "all", "anti", "cross", "ex", "half", "high", "ill", "long", "low", "multi", "non", "off",
+    "one", "open", "post", "pre", "quasi", "self", "semi", "short", "so", "step", "two", "well",
+];
+
+/// Second elements that are almost always attached with a hyphen.
+const COMPOUND_SUFFIXES: &[&str] = &[
+    "aware", "based", "bound", "centric", "class", "driven", "end", "facing", "free", "friendly",
+    "known", "led", "level", "like", "made", "minded", "old", "only", "oriented", "proof",
+    "related", "scale", "side", "specific", "style", "term", "time", "type", "wide", "year",
+];
+
+/// Remove every soft hyphen, rejoining words split at one.
+pub(crate) fn strip_soft_hyphens(text: &str) -> String {
+    if !text.contains(SOFT_HYPHEN) {
+        return text.to_string();
+    }
+    RE_SOFT_HYPHEN_BREAK
+        .replace_all(text, "")
+        .replace(SOFT_HYPHEN, "")
+}
+
+/// Rejoin words hyphenated across line ends. `compounds` lists extra hyphenated words to keep
+/// as written; returns the repaired text and how many line-end hyphens were handled.
+pub(crate) fn dehyphenate(text: &str, compounds: &[String]) -> (String, usize) {
+    let vocabulary = Vocabulary::new(text, compounds);
+    let mut out: Vec<String> = Vec::new();
+    let mut repaired = 0;
+    let mut lines = text.lines().map(str::to_string).peekable();
+    while let Some(mut line) = lines.next() {
+        while let Some(next) = lines.peek() {
+            let Some(joined) = join_split_word(&line, next, &vocabulary) else {
+                break;
+            };
+            line = joined;
+            lines.next();
+            repaired += 1;
+        }
+        out.push(line);
+    }
+    (out.join("\n"), repaired)
+}
+
+/// Join `line` and `next` when `line` ends in a hyphenated fragment that continues on `next`.
+fn join_split_word(line: &str, next: &str, vocabulary: &Vocabulary) -> Option<String> {
+    let line = line.trim_end();
+    let head = RE_TRAILING_FRAGMENT.captures(line)?.get(1)?;
+    let next = next.trim_start();
+    let tail = RE_LEADING_FRAGMENT.captures(next)?.get(1)?.as_str();
+    let rest = &next[tail.len()..];
+    let starts_upper = |word: &str| word.starts_with(char::is_uppercase);
+    let starts_digit = |word: &str| word.starts_with(|c: char| c.is_ascii_digit());
+    // A capital or a digit after the break is only a continuation when the head matches it:
+    // "Anglo-" / "Saxon" and "1990-" / "1995" are compounds, "going to-" / "Then" is not.
+    if (starts_upper(tail) && !starts_upper(head.as_str()))
+        || starts_digit(tail) != starts_digit(head.as_str())
+    {
+        return None;
+    }
+    // Ranges, proper-noun compounds and the last link of a longer compound
+    // ("state-of-the-" / "art") keep their hyphen.
+    let keep_hyphen = line[..head.start()].ends_with('-')
+        || starts_upper(tail)
+        || starts_digit(tail)
+        || vocabulary.is_compound(head.as_str(), tail);
+    let joiner = if keep_hyphen { "-" } else { "" };
+    Some(format!(
+        "{}{}{}{}{}",
+        &line[..head.start()],
+        head.as_str(),
+        joiner,
+        tail,
+        rest
+    ))
+}
+
+/// What the document itself says about how words are written.
+struct Vocabulary {
+    words: HashSet<String>,
+    compounds: HashSet<String>,
+    /// First and last elements of the document's hyphenated compounds.
+    heads: HashSet<String>,
+    tails: HashSet<String>,
+}
+
+impl Vocabulary {
+    fn new(text: &str, extra_compounds: &[String]) -> Self {
+        let mut words = HashSet::new();
+        let mut compounds: HashSet<String> = extra_compounds
+            .iter()
+            .map(|compound| compound.to_lowercase())
+            .collect();
+        for found in RE_WORD.find_iter(text) {
+            let word = found.as_str().to_lowercase();
+            // Hyphens at a line end say nothing; only mid-line ones count as evidence.
+            if text[found.end()..].starts_with("-\n") {
+                continue;
+            }
+            if word.contains('-') {
+                compounds.insert(word);
+            } else {
+                words.insert(word);
+            }
+        }
+        let heads = compounds
+            .iter()
+            .filter_map(|compound| compound.split('-').next())
+            .map(str::to_string)
+            .collect();
+        let tails = compounds
+            .iter()
+            .filter_map(|compound| compound.rsplit('-').next())
+            .map(str::to_string)
+            .collect();
+        Self {
+            words,
+            compounds,
+            heads,
+            tails,
+        }
+    }
+
+    /// Prefer the document's own spelling of the word, then how it uses the two parts in other
+    /// compounds, then the built-in lists; anything else is a word broken at a syllable.
+    fn is_compound(&self, head: &str, tail: &str) -> bool {
+        let head = head.to_lowercase();
+        let tail = tail.to_lowercase();
+        if self.compounds.contains(&format!("{head}-{tail}")) {
+            return true;
+        }
+        if self.words.contains(&format!("{head}{tail}")) {
+            return false;
+        }
+        self.heads.contains(&head)
+            || self.tails.contains(&tail)
+            || COMPOUND_PREFIXES.contains(&head.as_str())
+            || COMPOUND_SUFFIXES.contains(&tail.as_str())
+    }
+}
+
+/// Turn line breaks that look intentional into paragraph breaks, so unwrapping only joins
+/// soft-wrapped lines. Runs on the raw lines, before other stages change their length;
+/// returns the text and how many breaks were kept.
+pub(crate) fn separate_intentional_breaks(
+    text: &str,
+    cfg: &StructureConfig,
+    bullet_markers: &[String],
+) -> (String, usize) {
+    let lines: Vec<&str> = text.lines().collect();
+    let width = typical_width(&lines);
+    let mut out: Vec<&str> = Vec::with_capacity(lines.len());
+    let mut kept = 0;
+    for (idx, line) in lines.iter().enumerate() {
+        if idx > 0 {
+            let prev = lines[idx - 1];
+            // Consecutive list items are joined by the list stage.
+            let is_item = |text: &str| {
+                bullet_markers
+                    .iter()
+                    .any(|marker| text.trim_start().starts_with(marker.as_str()))
+            };
+            if !prev.trim().is_empty()
+                && !line.trim().is_empty()
+                && !is_item(line)
+                && is_hard_break(prev, line, width, cfg)
+            {
+                out.push("");
+                kept += 1;
+            }
+        }
+        out.push(line);
+    }
+    (out.join("\n"), kept)
+}
+
+/// The width the text was wrapped at: the 90th percentile length of lines that continue
+/// into another line, or 0 when there are too few to tell.
+fn typical_width(lines: &[&str]) -> usize {
+    let mut lengths: Vec<usize> = lines
+        .windows(2)
+        .filter(|pair| !pair[0].trim().is_empty() && !pair[1].trim().is_empty())
+        .map(|pair| pair[0].trim().chars().count())
+        .collect();
+    if lengths.len() < 3 {
+        return 0;
+    }
+    lengths.sort_unstable();
+    lengths[(lengths.len() - 1) * 9 / 10]
+}
+
+/// Whether the break between `line` and `next` (both non-blank) is intentional.
+fn is_hard_break(line: &str, next: &str, width: usize, cfg: &StructureConfig) -> bool {
+    let indent = |text: &str| text.chars().take_while(|c| c.is_whitespace()).count();
+    if matches!(cfg.paragraph_boundary, ParagraphBoundary::IndentedFirstLine)
+        && indent(next) > indent(line)
+    {
+        return true;
+    }
+    if cfg.wrap_detection == WrapDetection::JoinAll {
+        return false;
+    }
+
+    // A line that reaches the wrap width was wrapped, whatever comes next.
+    let length = line.trim().chars().count();
+    if width > 0 && length as f32 >= width as f32 * cfg.wrapped_line_ratio {
+        return false;
+    }
+    let line = line.trim_end();
+    let ends_sentence = line
+        .trim_end_matches(['"', '\'', ')', '”', '’'])
+        .ends_with(['.', '!', '?', ':', ';']);
+    // Trailing connectors ("and", "-", "|") carry on into the next line; a comma only does
+    // when the next line does not start anew, since verse lines often end in one.
+    let dangling = line.ends_with(['-', '–', '—', '|', '&', '/', '('])
+        || line
+            .rsplit(' ')
+            .next()
+            .is_some_and(|word| CONNECTORS.contains(&word.to_lowercase().as_str()));
+    let starts_anew = next
+        .trim_start()
+        .starts_with(|c: char| c.is_uppercase() || c.is_ascii_digit());
+    let indent_changes = !line.ends_with(',') && indent(next) != indent(line);
+    ends_sentence || (!dangling && (starts_anew || indent_changes))
+}
+
+/// Words a line does not end on unless the sentence continues.
+const CONNECTORS: &[&str] = &[
+    "a", "an", "and", "as", "at", "by", "for", "from", "in", "of", "on", "or", "the", "to", "with",
+];